@@ -2,7 +2,9 @@ mod util;
 
 extern crate proc_macro;
 
+use crate::util::factory::expand_factory;
 use crate::util::path::PathAttr;
+use crate::util::serialize::expand_serializable;
 use crate::util::stateful::expand_stateful;
 use proc_macro::TokenStream as RawStream;
 use proc_macro2::Ident;
@@ -30,9 +32,64 @@ use syn::DeriveInput;
 ///   }.init_app_state();
 /// }
 /// ```
-#[proc_macro_derive(InitAppState)]
+///
+/// # Dependencies
+/// The `app_state(depends_on(...))` attribute registers a factory for the state
+/// on application startup using `ctor`. The factory is built by `initialize_all()`
+/// after all listed dependencies have been initialized, by calling the `new`
+/// function of the struct with the dependencies in the listed order.
+/// Plain types are injected as `AppState`s, other states must be listed
+/// with their state type, e.g. `MutAppState<Db>`.
+///
+/// ```no_run
+/// use app_state::{AppState, AppStateTrait, MutAppState, InitAppState, initialize_all};
+///
+/// struct Config;
+/// struct Db;
+///
+/// #[derive(InitAppState)]
+/// #[app_state(depends_on(Config, MutAppState<Db>))]
+/// struct Service;
+///
+/// impl Service {
+///   fn new(config: AppState<Config>, db: MutAppState<Db>) -> Self {
+///     Service
+///   }
+/// }
+///
+/// fn main() {
+///   initialize_all().unwrap();
+/// }
+/// ```
+///
+/// # Snapshots
+/// With the `serde` feature of `app-state` enabled, the `app_state(serialize)` attribute
+/// registers the state to be included in `snapshot()` on application startup.
+/// The struct must implement `Serialize` and `Deserialize`.
+/// If the attribute is combined with several of the derive macros, `snapshot()`
+/// and `restore()` return an error, as each of them registers a different kind of state.
+///
+/// ```no_run
+/// use app_state::{AppState, InitAppState, snapshot};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(InitAppState, Serialize, Deserialize)]
+/// #[app_state(serialize)]
+/// struct Settings {
+///   volume: u32,
+/// }
+/// ```
+#[proc_macro_derive(InitAppState, attributes(app_state))]
 pub fn init_app_state(input: RawStream) -> RawStream {
     let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    let factory = match expand_factory(&input, quote! { app_state::AppState }) {
+        Ok(factory) => factory,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let serializable = match expand_serializable(&input, quote! { app_state::AppState }) {
+        Ok(serializable) => serializable,
+        Err(err) => return err.to_compile_error().into(),
+    };
     let name = input.ident;
 
     #[cfg(feature = "log")]
@@ -49,6 +106,9 @@ pub fn init_app_state(input: RawStream) -> RawStream {
                 AppState::init(self);
             }
         }
+
+        #factory
+        #serializable
     };
     gen.into()
 }
@@ -71,9 +131,21 @@ pub fn init_app_state(input: RawStream) -> RawStream {
 ///   }.init_mut_app_state();
 /// }
 /// ```
-#[proc_macro_derive(InitMutAppState)]
+///
+/// The `app_state(depends_on(...))` attribute registers a factory
+/// for the state and `app_state(serialize)` includes the state in snapshots.
+/// See `InitAppState` for details.
+#[proc_macro_derive(InitMutAppState, attributes(app_state))]
 pub fn init_mut_app_state(input: RawStream) -> RawStream {
     let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    let factory = match expand_factory(&input, quote! { app_state::MutAppState }) {
+        Ok(factory) => factory,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let serializable = match expand_serializable(&input, quote! { app_state::MutAppState }) {
+        Ok(serializable) => serializable,
+        Err(err) => return err.to_compile_error().into(),
+    };
     let name = input.ident;
 
     #[cfg(feature = "log")]
@@ -90,6 +162,65 @@ pub fn init_mut_app_state(input: RawStream) -> RawStream {
                 MutAppState::init(self);
             }
         }
+
+        #factory
+        #serializable
+    };
+    gen.into()
+}
+
+/// Derive macro for `InitRwAppState`.
+/// Allows you to initialize app states with `init_rw_app_state`.
+///
+/// # Example
+/// ```no_run
+/// use app_state::{stateful, RwAppState, InitRwAppState};
+///
+/// #[derive(InitRwAppState)]
+/// struct State {
+///   name: String,
+/// }
+///
+/// fn main() {
+///   State {
+///     name: "Hello".to_string(),
+///   }.init_rw_app_state();
+/// }
+/// ```
+///
+/// The `app_state(depends_on(...))` attribute registers a factory
+/// for the state and `app_state(serialize)` includes the state in snapshots.
+/// See `InitAppState` for details.
+#[proc_macro_derive(InitRwAppState, attributes(app_state))]
+pub fn init_rw_app_state(input: RawStream) -> RawStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    let factory = match expand_factory(&input, quote! { app_state::RwAppState }) {
+        Ok(factory) => factory,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let serializable = match expand_serializable(&input, quote! { app_state::RwAppState }) {
+        Ok(serializable) => serializable,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let name = input.ident;
+
+    #[cfg(feature = "log")]
+    let log = quote! {
+        log::debug!("Initializing reader-writer app state {}", std::any::type_name::<#name>());
+    };
+    #[cfg(not(feature = "log"))]
+    let log = quote! {};
+
+    let gen = quote! {
+        impl InitRwAppState for #name {
+            fn init_rw_app_state(self) {
+                #log
+                RwAppState::init(self);
+            }
+        }
+
+        #factory
+        #serializable
     };
     gen.into()
 }
@@ -102,6 +233,23 @@ pub fn init_mut_app_state(input: RawStream) -> RawStream {
 /// if they are not already initialized. This requires
 /// the specified states to implement `Default`.
 ///
+/// ## `named`
+/// A list of argument names mapped to state names.
+/// The named states are injected instead of the unnamed states.
+/// See `AppStateTrait::init_named` for details.
+///
+/// ## `timeout`
+/// A list of `MutAppStateLock` argument names mapped to durations, e.g. `"100ms"`.
+/// If the state can not be locked in time, the function returns
+/// `StateError::LockTimeout` converted using `Into` if it returns a `Result`
+/// and panics otherwise. Supported units are `ns`, `us`, `ms`, `s` and `m`.
+///
+/// # Locking multiple states
+/// If more than one `MutAppStateLock` is injected, the states are locked at once
+/// using `MutAppState::lock_many`, which always locks them in the same order,
+/// regardless of the order of the arguments. Locks with a `timeout` are acquired
+/// separately, in the order of the arguments.
+///
 /// # Examples
 /// ## Injecting multiple states
 /// ```no_run
@@ -118,6 +266,13 @@ pub fn init_mut_app_state(input: RawStream) -> RawStream {
 ///   // ...
 /// }
 ///
+/// #[stateful]
+/// fn bar(rw_state: RwAppState<SomeState>,
+///   read_state: RwAppStateReadLock<SomeMutState>,
+///   mut write_state: RwAppStateWriteLock<SomeOtherState>) {
+///   // ...
+/// }
+///
 /// fn main() {
 ///   AppState::init(SomeState);
 ///   MutAppState::init(SomeMutState);
@@ -127,6 +282,63 @@ pub fn init_mut_app_state(input: RawStream) -> RawStream {
 /// }
 /// ```
 ///
+/// ## Injecting trait objects
+/// ```no_run
+/// use app_state::{AppState, stateful};
+/// use std::sync::Arc;
+///
+/// trait Storage: Send + Sync {}
+///
+/// struct InMemoryStorage;
+/// impl Storage for InMemoryStorage {}
+///
+/// #[stateful]
+/// fn foo(storage: AppState<dyn Storage>) {
+///   // ...
+/// }
+///
+/// fn main() {
+///   AppState::<dyn Storage>::init_arc(Arc::new(InMemoryStorage));
+///
+///   foo();
+/// }
+/// ```
+///
+/// ## Injecting named states
+/// ```no_run
+/// use app_state::{AppState, AppStateTrait, stateful};
+///
+/// struct Pool;
+///
+/// #[stateful(named(primary = "primary", replica = "replica"))]
+/// fn foo(primary: AppState<Pool>, replica: AppState<Pool>) {
+///   // ...
+/// }
+///
+/// fn main() {
+///   AppState::init_named("primary", Pool);
+///   AppState::init_named("replica", Pool);
+///
+///   foo();
+/// }
+/// ```
+///
+/// ## Injecting states into async functions
+/// Asynchronous states (requires the `tokio` feature) can be injected
+/// into async functions. Locks are acquired asynchronously.
+/// ```no_run
+/// use app_state::{AsyncMutAppState, AsyncMutAppStateLock, stateful};
+///
+/// struct SomeState;
+/// struct SomeOtherState;
+///
+/// #[stateful]
+/// async fn foo(state: AsyncMutAppState<SomeState>,
+///   mut other_state: AsyncMutAppStateLock<SomeOtherState>) {
+///   // ...
+/// }
+/// ```
+///
 /// ## Injecting states with default values
 /// ```no_run
 /// use app_state::{AppState, MutAppState, stateful};
@@ -151,7 +363,21 @@ pub fn init_mut_app_state(input: RawStream) -> RawStream {
 ///   foo();
 /// }
 /// ```
+///
+/// ## Injecting locks with a timeout
+/// ```no_run
+/// use app_state::{MutAppStateLock, StateError, stateful};
+///
+/// struct SomeState;
+///
+/// #[stateful(timeout(state = "100ms"))]
+/// fn foo(mut state: MutAppStateLock<SomeState>) -> Result<(), StateError> {
+///   // ...
+///   Ok(())
+/// }
+/// ```
 #[proc_macro_attribute]
+#[allow(clippy::to_string_in_format_args)]
 pub fn stateful(args: RawStream, input: RawStream) -> RawStream {
     let args = syn::parse_macro_input!(args as PathAttr);
 
@@ -167,6 +393,45 @@ pub fn stateful(args: RawStream, input: RawStream) -> RawStream {
     }
 }
 
+/// Run a test with its own isolated state store.
+/// All states initialized in the test are only visible to the
+/// test's thread and dropped once the test finishes.
+/// This allows tests using the same state types to run in parallel.
+///
+/// See `StateStore::isolated` for details.
+///
+/// # Example
+/// ```no_run
+/// use app_state::{AppState, AppStateTrait};
+///
+/// struct State {
+///   name: String,
+/// }
+///
+/// #[app_state::test]
+/// fn test_state() {
+///   AppState::init(State { name: "Hello".to_string() });
+///   assert_eq!(AppState::<State>::get().name, "Hello");
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn test(_: RawStream, input: RawStream) -> RawStream {
+    let mut item = syn::parse_macro_input!(input as syn::ItemFn);
+    let stmt = match syn::parse2::<syn::Stmt>(quote! {
+        let __app_state_isolated_store = app_state::StateStore::isolated();
+    }) {
+        Ok(stmt) => stmt,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    item.block.stmts.insert(0, stmt);
+
+    (quote! {
+        #[::core::prelude::v1::test]
+        #item
+    })
+    .into()
+}
+
 fn get_default_state_values(input: DeriveInput) -> syn::Result<(Ident, Ident)> {
     let name = input.ident.clone();
 
@@ -189,6 +454,7 @@ fn get_default_state_values(input: DeriveInput) -> syn::Result<(Ident, Ident)> {
 /// Initialize the default state of the annotated struct
 /// on application startup using `ctor`.
 /// The default state is the result of calling `Default::default()`.
+/// If the struct implements `StateLifecycle`, its hooks are registered as well.
 ///
 /// # Example
 /// ```no_run
@@ -220,8 +486,10 @@ pub fn init_default_state(_: RawStream, input: RawStream) -> RawStream {
 
         #[ctor::ctor]
         fn #id() {
+            use app_state::__private::{InitLifecycle as _, InitPlain as _};
+
             #log
-            AppState::init(#name::default());
+            (&app_state::__private::InitWith::<AppState<#name>, #name>::new()).init_state();
         }
     })
     .into()
@@ -230,6 +498,7 @@ pub fn init_default_state(_: RawStream, input: RawStream) -> RawStream {
 /// Initialize the default state of the annotated struct
 /// on application startup using `ctor`.
 /// The default state is the result of calling `Default::default()`.
+/// If the struct implements `StateLifecycle`, its hooks are registered as well.
 ///
 /// # Example
 /// ```no_run
@@ -261,8 +530,54 @@ pub fn init_default_mut_state(_: RawStream, input: RawStream) -> RawStream {
 
         #[ctor::ctor]
         fn #id() {
+            use app_state::__private::{InitLifecycle as _, InitPlain as _};
+
+            #log
+            (&app_state::__private::InitWith::<MutAppState<#name>, #name>::new()).init_state();
+        }
+    })
+    .into()
+}
+
+/// Initialize the default state of the annotated struct
+/// on application startup using `ctor`.
+/// The default state is the result of calling `Default::default()`.
+/// If the struct implements `StateLifecycle`, its hooks are registered as well.
+///
+/// # Example
+/// ```no_run
+/// use app_state::{RwAppState, init_default_rw_state, AppStateTrait};
+///
+/// #[init_default_rw_state]
+/// #[derive(Default)]
+/// struct SomeState {
+///   name: String,
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn init_default_rw_state(_: RawStream, input: RawStream) -> RawStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    let (name, id) = match get_default_state_values(input.clone()) {
+        Ok(res) => res,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    #[cfg(feature = "log")]
+    let log = quote! {
+        log::debug!("Initializing reader-writer app state {} before main", std::any::type_name::<#name>());
+    };
+    #[cfg(not(feature = "log"))]
+    let log = quote! {};
+
+    (quote! {
+        #input
+
+        #[ctor::ctor]
+        fn #id() {
+            use app_state::__private::{InitLifecycle as _, InitPlain as _};
+
             #log
-            MutAppState::init(#name::default());
+            (&app_state::__private::InitWith::<RwAppState<#name>, #name>::new()).init_state();
         }
     })
     .into()
@@ -0,0 +1,118 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::{quote, ToTokens};
+use rand::Rng;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parenthesized, DeriveInput, Token, Type};
+
+/// The `app_state` attribute of the derive macros,
+/// e.g. `#[app_state(depends_on(Config, MutAppState<Db>), serialize)]`.
+#[derive(Default)]
+pub(crate) struct StateAttr {
+    pub(crate) depends_on: Option<Vec<Type>>,
+    pub(crate) serialize: Option<Ident>,
+}
+
+impl Parse for StateAttr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut state_attr = StateAttr::default();
+
+        while !input.is_empty() {
+            let ident = input.parse::<Ident>()?;
+            match &*ident.to_string() {
+                "depends_on" => {
+                    let dependencies;
+                    parenthesized!(dependencies in input);
+
+                    state_attr.depends_on = Some(
+                        Punctuated::<Type, Token![,]>::parse_terminated(&dependencies)?
+                            .into_iter()
+                            .collect(),
+                    );
+                }
+                "serialize" => state_attr.serialize = Some(ident),
+                _ => {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "unexpected identifier, expected any of: depends_on, serialize",
+                    ))
+                }
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(state_attr)
+    }
+}
+
+/// Parses all `app_state` attributes of `input`.
+/// Options given in later attributes override earlier ones.
+pub(crate) fn state_attr(input: &DeriveInput) -> syn::Result<StateAttr> {
+    let mut res = StateAttr::default();
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("app_state"))
+    {
+        let state_attr = attr.parse_args::<StateAttr>()?;
+        if state_attr.depends_on.is_some() {
+            res.depends_on = state_attr.depends_on;
+        }
+        if state_attr.serialize.is_some() {
+            res.serialize = state_attr.serialize;
+        }
+    }
+
+    Ok(res)
+}
+
+/// Returns the state type of a dependency.
+/// Plain types are read-only states, e.g. `Config` is `AppState<Config>`.
+fn dependency_state(ty: &Type) -> TokenStream {
+    const STATE_TYPES: [&str; 4] = ["AppState", "MutAppState", "RwAppState", "SwapAppState"];
+
+    if let Type::Path(path) = ty {
+        if let Some(segment) = path.path.segments.last() {
+            if STATE_TYPES.contains(&&*segment.ident.to_string())
+                && matches!(segment.arguments, syn::PathArguments::AngleBracketed(_))
+            {
+                return ty.to_token_stream();
+            }
+        }
+    }
+
+    quote! { app_state::AppState<#ty> }
+}
+
+/// Registers a factory for the state `state<input>` on application startup
+/// if the `app_state(depends_on(...))` attribute is present.
+/// The factory calls `new` with the dependencies in the declared order.
+pub(crate) fn expand_factory(input: &DeriveInput, state: TokenStream) -> syn::Result<TokenStream> {
+    let dependencies = match state_attr(input)?.depends_on {
+        Some(dependencies) => dependencies
+            .iter()
+            .map(dependency_state)
+            .collect::<Vec<_>>(),
+        None => return Ok(quote! {}),
+    };
+
+    let name = &input.ident;
+    let mut rng = rand::thread_rng();
+    let id = Ident::new(
+        &format!("__register_state_factory_{}", rng.gen::<u32>()),
+        proc_macro2::Span::call_site(),
+    );
+
+    Ok(quote! {
+        #[ctor::ctor]
+        fn #id() {
+            app_state::register_factory(app_state::StateFactory::new::<#state<#name>, #name>(
+                vec![#(app_state::Dependency::of::<#dependencies>()),*],
+                |store| #name::new(#(store.get::<#dependencies>()),*),
+            ));
+        }
+    })
+}
@@ -1,3 +1,6 @@
+pub(crate) mod factory;
 pub(crate) mod path;
+pub(crate) mod serialize;
 pub(crate) mod stateful;
+#[allow(clippy::module_inception)]
 pub(crate) mod util;
@@ -1,11 +1,30 @@
 use proc_macro2::Ident;
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
-use syn::{parenthesized, Token};
+use syn::{parenthesized, LitStr, Token};
+
+/// An argument name mapped to a value, e.g. `pool = "replica"`.
+#[derive(Debug)]
+pub(crate) struct ArgValue {
+    pub(crate) arg: Ident,
+    pub(crate) value: LitStr,
+}
+
+impl Parse for ArgValue {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let arg = input.parse::<Ident>()?;
+        input.parse::<Token![=]>()?;
+        let value = input.parse::<LitStr>()?;
+
+        Ok(ArgValue { arg, value })
+    }
+}
 
 #[derive(Default, Debug)]
 pub(crate) struct PathAttr {
     pub(crate) init: Option<Vec<Ident>>,
+    pub(crate) named: Option<Vec<ArgValue>>,
+    pub(crate) timeout: Option<Vec<ArgValue>>,
     #[cfg(feature = "log")]
     pub(crate) log_member: Option<Ident>,
     #[cfg(feature = "log")]
@@ -13,9 +32,10 @@ pub(crate) struct PathAttr {
 }
 
 impl Parse for PathAttr {
+    #[allow(clippy::unnecessary_unwrap)]
     fn parse(input: ParseStream) -> syn::Result<Self> {
         const EXPECTED_ATTRIBUTE_MESSAGE: &str =
-            "unexpected identifier, expected any of: init, log_member, no_log";
+            "unexpected identifier, expected any of: init, named, timeout, log_member, no_log";
         let mut path_attr = PathAttr::default();
 
         while !input.is_empty() {
@@ -44,6 +64,38 @@ impl Parse for PathAttr {
                         ));
                     }
                 }
+                "named" => {
+                    let named;
+                    parenthesized!(named in input);
+
+                    path_attr.named = Some(
+                        Punctuated::<ArgValue, Token![,]>::parse_terminated(&named)
+                            .map(|punctuated| punctuated.into_iter().collect::<Vec<_>>())?,
+                    );
+
+                    if path_attr.named.as_ref().unwrap().is_empty() {
+                        return Err(syn::Error::new(
+                            ident.span(),
+                            "expected at least one named state",
+                        ));
+                    }
+                }
+                "timeout" => {
+                    let timeout;
+                    parenthesized!(timeout in input);
+
+                    path_attr.timeout = Some(
+                        Punctuated::<ArgValue, Token![,]>::parse_terminated(&timeout)
+                            .map(|punctuated| punctuated.into_iter().collect::<Vec<_>>())?,
+                    );
+
+                    if path_attr.timeout.as_ref().unwrap().is_empty() {
+                        return Err(syn::Error::new(
+                            ident.span(),
+                            "expected at least one state timeout",
+                        ));
+                    }
+                }
                 #[cfg(feature = "log")]
                 "log_member" => {
                     path_attr.log_member = Some(Ident::new("log_member", ident.span()));
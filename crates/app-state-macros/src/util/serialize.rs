@@ -0,0 +1,47 @@
+use crate::util::factory::state_attr;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::DeriveInput;
+
+/// Registers the state `state<input>` to be included in snapshots on application startup
+/// if the `app_state(serialize)` attribute is present.
+#[cfg(feature = "serde")]
+pub(crate) fn expand_serializable(
+    input: &DeriveInput,
+    state: TokenStream,
+) -> syn::Result<TokenStream> {
+    use proc_macro2::Ident;
+    use rand::Rng;
+
+    if state_attr(input)?.serialize.is_none() {
+        return Ok(quote! {});
+    }
+
+    let name = &input.ident;
+    let mut rng = rand::thread_rng();
+    let id = Ident::new(
+        &format!("__register_serializable_state_{}", rng.gen::<u32>()),
+        proc_macro2::Span::call_site(),
+    );
+
+    Ok(quote! {
+        #[ctor::ctor]
+        fn #id() {
+            app_state::register_serializable::<#state<#name>>();
+        }
+    })
+}
+
+#[cfg(not(feature = "serde"))]
+pub(crate) fn expand_serializable(
+    input: &DeriveInput,
+    _state: TokenStream,
+) -> syn::Result<TokenStream> {
+    match state_attr(input)?.serialize {
+        Some(ident) => Err(syn::Error::new(
+            ident.span(),
+            "the serialize option requires the serde feature of app-state",
+        )),
+        None => Ok(quote! {}),
+    }
+}
@@ -11,6 +11,15 @@ enum StateIdent {
     AppState,
     MutAppState,
     MutAppStateLock,
+    RwAppState,
+    RwAppStateReadLock,
+    RwAppStateWriteLock,
+    SwapAppState,
+    AsyncMutAppState,
+    AsyncMutAppStateLock,
+    AsyncRwAppState,
+    AsyncRwAppStateReadLock,
+    AsyncRwAppStateWriteLock,
 }
 
 impl StateIdent {
@@ -19,17 +28,65 @@ impl StateIdent {
             "AppState" => Ok(StateIdent::AppState),
             "MutAppState" => Ok(StateIdent::MutAppState),
             "MutAppStateLock" => Ok(StateIdent::MutAppStateLock),
+            "RwAppState" => Ok(StateIdent::RwAppState),
+            "RwAppStateReadLock" => Ok(StateIdent::RwAppStateReadLock),
+            "RwAppStateWriteLock" => Ok(StateIdent::RwAppStateWriteLock),
+            "SwapAppState" => Ok(StateIdent::SwapAppState),
+            "AsyncMutAppState" => Ok(StateIdent::AsyncMutAppState),
+            "AsyncMutAppStateLock" => Ok(StateIdent::AsyncMutAppStateLock),
+            "AsyncRwAppState" => Ok(StateIdent::AsyncRwAppState),
+            "AsyncRwAppStateReadLock" => Ok(StateIdent::AsyncRwAppStateReadLock),
+            "AsyncRwAppStateWriteLock" => Ok(StateIdent::AsyncRwAppStateWriteLock),
             _ => Err(syn::Error::new(segment.span(), "Invalid state type")),
         }
     }
 
+    pub fn is_state_ident(segment: &PathSegment) -> bool {
+        Self::new(segment).is_ok()
+    }
+
     pub fn to_token_stream(&self) -> TokenStream {
         match self {
             StateIdent::AppState => quote! { AppState },
             StateIdent::MutAppState => quote! { MutAppState },
             StateIdent::MutAppStateLock => quote! { MutAppStateLock },
+            StateIdent::RwAppState => quote! { RwAppState },
+            StateIdent::RwAppStateReadLock => quote! { RwAppStateReadLock },
+            StateIdent::RwAppStateWriteLock => quote! { RwAppStateWriteLock },
+            StateIdent::SwapAppState => quote! { SwapAppState },
+            StateIdent::AsyncMutAppState => quote! { AsyncMutAppState },
+            StateIdent::AsyncMutAppStateLock => quote! { AsyncMutAppStateLock },
+            StateIdent::AsyncRwAppState => quote! { AsyncRwAppState },
+            StateIdent::AsyncRwAppStateReadLock => quote! { AsyncRwAppStateReadLock },
+            StateIdent::AsyncRwAppStateWriteLock => quote! { AsyncRwAppStateWriteLock },
+        }
+    }
+
+    /// Returns the state type the lock is obtained from,
+    /// if this is a lock type.
+    pub fn lock_source(&self) -> Option<TokenStream> {
+        match self {
+            StateIdent::MutAppStateLock => Some(quote! { MutAppState }),
+            StateIdent::RwAppStateReadLock | StateIdent::RwAppStateWriteLock => {
+                Some(quote! { RwAppState })
+            }
+            StateIdent::AsyncMutAppStateLock => Some(quote! { AsyncMutAppState }),
+            StateIdent::AsyncRwAppStateReadLock | StateIdent::AsyncRwAppStateWriteLock => {
+                Some(quote! { AsyncRwAppState })
+            }
+            _ => None,
         }
     }
+
+    /// Whether this is a lock type which must be awaited.
+    pub fn is_async_lock(&self) -> bool {
+        matches!(
+            self,
+            StateIdent::AsyncMutAppStateLock
+                | StateIdent::AsyncRwAppStateReadLock
+                | StateIdent::AsyncRwAppStateWriteLock
+        )
+    }
 }
 
 fn get_type(
@@ -44,10 +101,7 @@ fn get_type(
 
         if let Type::Path(path) = &*typed.ty {
             for segment in &path.path.segments {
-                if segment.ident == "AppState"
-                    || segment.ident == "MutAppState"
-                    || segment.ident == "MutAppStateLock"
-                {
+                if StateIdent::is_state_ident(segment) {
                     let state_type = StateIdent::new(segment)?;
                     let is_mut = if is_mut(&typed.pat) {
                         quote! { mut }
@@ -57,9 +111,9 @@ fn get_type(
 
                     if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
                         for arg in &args.args {
-                            if let syn::GenericArgument::Type(Type::Path(path)) = arg {
-                                let ident = path.path.segments[0].ident.to_string().parse()?;
-                                return Ok(Some((name, state_type, ident, is_mut)));
+                            if let syn::GenericArgument::Type(ty) = arg {
+                                let ty = ty.to_token_stream();
+                                return Ok(Some((name, state_type, ty, is_mut)));
                             }
                         }
                     }
@@ -71,6 +125,7 @@ fn get_type(
     Ok(None)
 }
 
+#[allow(clippy::cmp_owned)]
 fn should_init(args: &PathAttr, name: &TokenStream) -> bool {
     args.init
         .as_ref()
@@ -78,7 +133,70 @@ fn should_init(args: &PathAttr, name: &TokenStream) -> bool {
         .unwrap_or(false)
 }
 
+fn state_name(args: &PathAttr, name: &TokenStream) -> Option<syn::LitStr> {
+    args.named.as_ref().and_then(|n| {
+        n.iter()
+            .find(|x| x.arg == name.to_string())
+            .map(|x| x.value.clone())
+    })
+}
+
+/// Parses a duration like `100ms` into a `Duration` expression.
+/// Supported units are `ns`, `us`, `ms`, `s` and `m`.
+fn parse_timeout(value: &syn::LitStr) -> syn::Result<TokenStream> {
+    let text = value.value();
+    let unit_start = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (amount, unit) = text.split_at(unit_start);
+
+    let amount = amount
+        .parse::<u64>()
+        .map_err(|_| syn::Error::new(value.span(), "expected a duration like \"100ms\""))?;
+    let nanos = match unit.trim() {
+        "ns" => Some(amount),
+        "us" => amount.checked_mul(1_000),
+        "ms" => amount.checked_mul(1_000_000),
+        "s" => amount.checked_mul(1_000_000_000),
+        "m" => amount.checked_mul(60_000_000_000),
+        _ => {
+            return Err(syn::Error::new(
+                value.span(),
+                "unknown duration unit, expected any of: ns, us, ms, s, m",
+            ))
+        }
+    }
+    .ok_or_else(|| syn::Error::new(value.span(), "duration is too long"))?;
+
+    Ok(quote! { ::std::time::Duration::from_nanos(#nanos) })
+}
+
+fn state_timeout<'a>(args: &'a PathAttr, name: &TokenStream) -> Option<&'a syn::LitStr> {
+    args.timeout.as_ref().and_then(|t| {
+        t.iter()
+            .find(|x| x.arg == name.to_string())
+            .map(|x| &x.value)
+    })
+}
+
+/// Whether the function returns a `Result`.
+fn returns_result(output: &syn::ReturnType) -> bool {
+    if let syn::ReturnType::Type(_, ty) = output {
+        if let Type::Path(path) = ty.as_ref() {
+            return path
+                .path
+                .segments
+                .last()
+                .map(|s| s.ident == "Result")
+                .unwrap_or(false);
+        }
+    }
+
+    false
+}
+
 #[cfg(feature = "log")]
+#[allow(clippy::cmp_owned)]
 fn references_self(inputs: &Punctuated<FnArg, Token![,]>) -> bool {
     inputs.iter().any(|i| {
         if let FnArg::Receiver(r) = i {
@@ -95,6 +213,12 @@ fn references_self(inputs: &Punctuated<FnArg, Token![,]>) -> bool {
     })
 }
 
+#[allow(
+    clippy::filter_map_identity,
+    clippy::needless_borrow,
+    clippy::to_string_in_format_args,
+    clippy::useless_conversion
+)]
 pub(crate) fn expand_stateful(input: TokenStream, args: PathAttr) -> syn::Result<TokenStream> {
     let mut item = syn::parse2::<syn::Item>(input)?;
 
@@ -121,11 +245,15 @@ pub(crate) fn expand_stateful(input: TokenStream, args: PathAttr) -> syn::Result
             .map(|x| x.0)
             .collect::<_>();
 
-        // Check if all arguments marked as default are present
-        if let Some(not_found) = args.init.as_ref().and_then(|d| {
-            d.iter()
-                .find(|e1| !states.iter().any(|e2| e1.to_string() == e2.0.to_string()))
-        }) {
+        // Check if all arguments marked as default or named are present
+        if let Some(not_found) = args
+            .init
+            .iter()
+            .flatten()
+            .chain(args.named.iter().flatten().map(|n| &n.arg))
+            .chain(args.timeout.iter().flatten().map(|t| &t.arg))
+            .find(|e1| !states.iter().any(|e2| e1.to_string() == e2.0.to_string()))
+        {
             return Err(syn::Error::new(
                 not_found.span(),
                 format!(
@@ -177,8 +305,24 @@ pub(crate) fn expand_stateful(input: TokenStream, args: PathAttr) -> syn::Result
             }
         };
 
+        // Used to report the function holding a lock if a deadlock is detected
+        let lock_site = {
+            let fn_name = &item.sig.ident;
+            quote! { concat!(module_path!(), "::", stringify!(#fn_name)) }
+        };
+
+        // Locks without a timeout are acquired together in a canonical order
+        // if there are several of them, preventing lock order deadlocks
+        let is_grouped = |(var_name, state_type, _, _): &(TokenStream, StateIdent, _, _)| {
+            *state_type == StateIdent::MutAppStateLock && state_timeout(&args, var_name).is_none()
+        };
+        let group_locks = states.iter().filter(|s| is_grouped(s)).count() > 1;
+        let mut grouped = Vec::new();
+
         let mut statements = Vec::new();
-        for (var_name, state_type, type_name, is_mut) in states {
+        for state in states {
+            let grouped_lock = group_locks && is_grouped(&state);
+            let (var_name, state_type, type_name, is_mut) = state;
             let state_type_tokens = state_type.to_token_stream();
 
             #[cfg(feature = "log")]
@@ -207,29 +351,78 @@ pub(crate) fn expand_stateful(input: TokenStream, args: PathAttr) -> syn::Result
                 })?
             };
 
+            let name = state_name(&args, &var_name);
             let getter = if should_init(&args, &var_name) {
                 #[cfg(feature = "log")]
                 if args.no_log.is_none() {
                     statements.push(log_initializing_state);
                 }
 
-                quote! { get_or_insert_default }
+                match name {
+                    Some(name) => quote! { get_or_insert_default_named(#name) },
+                    None => quote! { get_or_insert_default() },
+                }
             } else {
-                quote! { get }
+                match name {
+                    Some(name) => quote! { get_named(#name) },
+                    None => quote! { get() },
+                }
             };
 
-            if state_type == StateIdent::MutAppStateLock {
+            if let Some(source) = state_type.lock_source() {
                 #[cfg(feature = "log")]
                 if args.no_log.is_none() {
                     statements.push(log_injecting_state);
                 }
 
                 statements.push(syn::parse2::<syn::Stmt>(quote! {
-                    let #var_name = MutAppState::<#type_name>::#getter();
+                    let #var_name = #source::<#type_name>::#getter;
                 })?);
 
+                if grouped_lock {
+                    grouped.push((var_name, is_mut));
+                    continue;
+                }
+
+                let timeout = state_timeout(&args, &var_name);
+                let lock = if let Some(timeout) = timeout {
+                    if state_type != StateIdent::MutAppStateLock {
+                        return Err(syn::Error::new(
+                            timeout.span(),
+                            "timeouts are only supported for MutAppStateLock",
+                        ));
+                    }
+
+                    let duration = parse_timeout(timeout)?;
+                    let on_error = if returns_result(&item.sig.output) {
+                        quote! { return Err(err.into()) }
+                    } else {
+                        quote! { panic!("{}", err) }
+                    };
+
+                    quote! {
+                        match #state_type_tokens::with_timeout_in(&#var_name, #duration, #lock_site) {
+                            Ok(lock) => lock,
+                            Err(err) => #on_error,
+                        }
+                    }
+                } else if state_type.is_async_lock() {
+                    if item.sig.asyncness.is_none() {
+                        return Err(syn::Error::new(
+                            var_name.span(),
+                            "async locks can only be injected into async functions",
+                        ));
+                    }
+
+                    quote! { #state_type_tokens::new(&#var_name).await }
+                } else if state_type == StateIdent::MutAppStateLock {
+                    quote! { #state_type_tokens::new_in(&#var_name, #lock_site) }
+                } else {
+                    quote! { #state_type_tokens::new(&#var_name) }
+                };
+
                 statements.push(syn::parse2::<syn::Stmt>(quote! {
-                    let #is_mut #var_name = MutAppStateLock::new(&#var_name);
+                    let #is_mut #var_name = #lock;
                 })?);
             } else {
                 #[cfg(feature = "log")]
@@ -238,11 +431,22 @@ pub(crate) fn expand_stateful(input: TokenStream, args: PathAttr) -> syn::Result
                 }
 
                 statements.push(syn::parse2::<syn::Stmt>(quote! {
-                    let #is_mut #var_name = #state_type_tokens::<#type_name>::#getter();
+                    let #is_mut #var_name = #state_type_tokens::<#type_name>::#getter;
                 })?);
             }
         }
 
+        if !grouped.is_empty() {
+            let bindings = grouped
+                .iter()
+                .map(|(var_name, is_mut)| quote! { #is_mut #var_name });
+            let states = grouped.iter().map(|(var_name, _)| quote! { &#var_name });
+
+            statements.push(syn::parse2::<syn::Stmt>(quote! {
+                let (#(#bindings,)*) = app_state::LockMany::lock_in((#(#states,)*), Some(#lock_site));
+            })?);
+        }
+
         statements.append(&mut item.block.stmts);
         item.block.stmts = statements;
     } else {
@@ -1,3 +1,4 @@
+#[allow(clippy::borrowed_box)]
 pub(crate) fn is_mut(pat: &Box<syn::Pat>) -> bool {
     if let syn::Pat::Ident(ident) = &**pat {
         ident.mutability.is_some()
@@ -23,10 +23,10 @@
 //! ```
 //!
 //! ### Using `derive`
-//! In order to avoid boilerplate code, the `InitAppState` and `InitMutAppState`
-//! traits can be derived for any struct. These traits provide the `init_app_state`
-//! and `init_mut_app_state` methods respectively which can be used to initialize
-//! the state more easily.
+//! In order to avoid boilerplate code, the `InitAppState`, `InitMutAppState`
+//! and `InitRwAppState` traits can be derived for any struct. These traits provide
+//! the `init_app_state`, `init_mut_app_state` and `init_rw_app_state` methods
+//! respectively which can be used to initialize the state more easily.
 //! ```rust
 //! use app_state::{AppState, MutAppState, AppStateTrait, InitAppState, InitMutAppState};
 //!
@@ -41,6 +41,36 @@
 //! }
 //! ```
 //!
+//! ### Declaring dependencies
+//! States which are built from other states can declare their dependencies
+//! using the `app_state(depends_on(...))` attribute. `initialize_all()`
+//! initializes all declared states in dependency order by calling their `new` function.
+//! ```rust
+//! use app_state::{AppState, AppStateTrait, InitAppState, initialize_all};
+//!
+//! struct Config {
+//!   url: String,
+//! }
+//!
+//! #[derive(InitAppState)]
+//! #[app_state(depends_on(Config))]
+//! struct Client {
+//!   url: String,
+//! }
+//!
+//! impl Client {
+//!   fn new(config: AppState<Config>) -> Self {
+//!     Client { url: config.url.clone() }
+//!   }
+//! }
+//!
+//! fn main() {
+//!   AppState::init(Config { url: "localhost".to_string() });
+//!   initialize_all().unwrap();
+//!   assert_eq!(AppState::<Client>::get().url, "localhost");
+//! }
+//! ```
+//!
 //! ## Read-only state
 //! App states internally use `Arc` to allow for thread-safe access.
 //! ```rust
@@ -90,6 +120,128 @@
 //! }
 //! ```
 //!
+//! With the `deadlock-detection` feature enabled, locking a state which is already
+//! held by the current thread panics instead of blocking forever. Every state
+//! locked while holding another state records the order of the two states, and
+//! locking them in the opposite order later panics, even if the two orders are
+//! never used at the same time. The panic message names the functions holding the locks.
+//!
+//! To lock several states at once without risking a deadlock, use `lock_all!` or
+//! `MutAppState::lock_many`, which lock the states in a canonical order.
+//! `stateful` does this automatically when injecting more than one `MutAppStateLock`.
+//!
+//! ## Reader-writer state
+//! Reader-writer states internally use a `RwLock`, allowing any number of
+//! threads to read the state at the same time. Writing to the state
+//! requires exclusive access.
+//! The state can be locked by calling `read()` or `write()` or by using the
+//! `RwAppStateReadLock` and `RwAppStateWriteLock` types.
+//! ```rust
+//! use app_state::{RwAppState, RwAppStateReadLock, RwAppStateWriteLock, AppStateTrait, stateful};
+//!
+//! struct MyState {
+//!   counter: u32,
+//! }
+//!
+//! #[stateful]
+//! fn read(state: RwAppStateReadLock<MyState>) {
+//!   println!("Counter: {}", state.counter);
+//! }
+//!
+//! #[stateful]
+//! fn write(mut state: RwAppStateWriteLock<MyState>) {
+//!   state.counter += 1;
+//! }
+//! ```
+//!
+//! ## Atomically replaceable state
+//! For states which are read often but replaced rarely, `SwapAppState` provides
+//! lock-free reads: `load()` returns a snapshot of the current version,
+//! while `store()` and `rcu()` atomically publish a new version.
+//! ```rust
+//! use app_state::{SwapAppState, AppStateTrait, stateful};
+//!
+//! struct Config {
+//!   verbose: bool,
+//! }
+//!
+//! #[stateful]
+//! fn func(config: SwapAppState<Config>) {
+//!   let snapshot = config.load();
+//!   config.rcu(|old| Config { verbose: !old.verbose });
+//! }
+//! ```
+//!
+//! ## Asynchronous state
+//! When the `tokio` feature is enabled, `AsyncMutAppState` and `AsyncRwAppState`
+//! can be used in asynchronous code. These states use the `tokio` synchronization
+//! primitives, their lock guards are `Send` and may be held across `.await` points.
+//! ```ignore
+//! use app_state::{AsyncMutAppState, AsyncMutAppStateLock, AppStateTrait, stateful};
+//!
+//! struct MyState {
+//!   counter: u32,
+//! }
+//!
+//! #[stateful]
+//! async fn func(mut state: AsyncMutAppStateLock<MyState>) {
+//!   state.counter += 1;
+//!   tokio::task::yield_now().await;
+//! }
+//! ```
+//!
+//! `MutAppState::watch()` returns a `tokio::sync::watch::Receiver` which receives
+//! a snapshot of a cloneable state whenever it is changed or re-initialized.
+//! ```rust
+//! use app_state::{MutAppState, AppStateTrait};
+//!
+//! #[derive(Clone, Default)]
+//! struct Config {
+//!   verbose: bool,
+//! }
+//!
+//! # #[cfg(feature = "tokio")]
+//! fn main() {
+//!   MutAppState::init(Config::default());
+//!   let config = MutAppState::<Config>::watch();
+//!
+//!   MutAppState::<Config>::get().get_mut().verbose = true;
+//!   assert!(config.borrow().verbose);
+//! }
+//! # #[cfg(not(feature = "tokio"))]
+//! # fn main() {}
+//! ```
+//!
+//! ## Trait objects
+//! Read-only states may also hold trait objects. These must be initialized
+//! from an existing `Arc` using `AppState::init_arc()`.
+//! ```rust
+//! use std::sync::Arc;
+//! use app_state::{AppState, AppStateTrait, stateful};
+//!
+//! trait Storage: Send + Sync {
+//!   fn name(&self) -> String;
+//! }
+//!
+//! struct InMemoryStorage;
+//!
+//! impl Storage for InMemoryStorage {
+//!   fn name(&self) -> String {
+//!     "in-memory".to_string()
+//!   }
+//! }
+//!
+//! #[stateful]
+//! fn func(storage: AppState<dyn Storage>) {
+//!   println!("Storage: {}", storage.name());
+//! }
+//!
+//! fn main() {
+//!   AppState::<dyn Storage>::init_arc(Arc::new(InMemoryStorage));
+//!   func();
+//! }
+//! ```
+//!
 //! ## Get the state manually
 //! You can also get the state manually by calling `AppState::get()` or `MutAppState::get()`.
 //! ```no_run
@@ -104,6 +256,266 @@
 //!   let mut_state = MutAppState::<MyState>::get();
 //! }
 //! ```
+//!
+//! ## Independent state stores
+//! All states are stored in a global `StateStore` by default.
+//! If you need multiple independent sets of states, you can create
+//! your own `StateStore` instances.
+//! ```rust
+//! use app_state::{AppState, StateStore};
+//!
+//! struct MyState {
+//!   counter: u32,
+//! }
+//!
+//! fn main() {
+//!   let store = StateStore::new();
+//!   store.init::<AppState<_>, _>(MyState { counter: 0 });
+//!
+//!   let state = store.get::<AppState<MyState>>();
+//! }
+//! ```
+//!
+//! ## Transactions
+//! `transaction()` runs a closure on working copies of several mutable states and writes
+//! the changed copies back only if the closure returns `Ok`. The states are locked in a
+//! canonical order when the changes are written. If another thread changed or replaced one
+//! of the states in the meantime, the closure is run again, hence it may run more than once.
+//! After `MAX_TRANSACTION_ATTEMPTS` conflicting attempts, the transaction is aborted with
+//! `StateError::TransactionConflict`, which is converted into the error type of the closure.
+//! ```rust
+//! use app_state::{transaction, MutAppState, AppStateTrait};
+//! use std::error::Error;
+//!
+//! #[derive(Clone)]
+//! struct Account {
+//!   balance: u32,
+//! }
+//!
+//! #[derive(Clone, Default)]
+//! struct Ledger {
+//!   entries: Vec<u32>,
+//! }
+//!
+//! fn main() {
+//!   MutAppState::init(Account { balance: 10 });
+//!   MutAppState::init(Ledger::default());
+//!
+//!   let res = transaction(|tx| -> Result<u32, Box<dyn Error>> {
+//!     let mut account = tx.lock::<Account>();
+//!     account.balance = account.balance.checked_sub(4).ok_or("Insufficient funds")?;
+//!     tx.lock::<Ledger>().entries.push(4);
+//!     Ok(account.balance)
+//!   });
+//!
+//!   assert_eq!(res.unwrap(), 6);
+//! }
+//! ```
+//!
+//! ## Versions
+//! Every `MutAppState` has a version which is incremented whenever a lock which changed the
+//! state is released. `MutAppState::compare_and_update` only applies a change if the state
+//! still has the expected version, so that a value can be read, processed without holding
+//! the lock and written back without overwriting concurrent changes.
+//! ```rust
+//! use app_state::{MutAppState, AppStateTrait, StateError};
+//!
+//! #[derive(Clone, Default)]
+//! struct Counter {
+//!   value: u32,
+//! }
+//!
+//! fn main() {
+//!   let state = MutAppState::<Counter>::get_or_insert_default();
+//!   let (version, counter) = {
+//!     let lock = state.get_mut();
+//!     (lock.version(), lock.clone())
+//!   };
+//!
+//!   let value = counter.value + 1;
+//!   match state.compare_and_update(version, |counter| counter.value = value) {
+//!     Ok(()) => println!("Updated the counter"),
+//!     Err(StateError::VersionMismatch { .. }) => println!("The counter has been changed"),
+//!     Err(err) => panic!("{}", err),
+//!   }
+//! }
+//! ```
+//!
+//! ## Undo and redo
+//! A `HistoryMutAppState` records the previous version of its value whenever a lock which
+//! changed it is released. Changes can be reverted using `undo()` and reapplied using `redo()`,
+//! `begin_group()` merges several changes into a single undo step.
+//! ```rust
+//! use app_state::{HistoryMutAppState, AppStateTrait};
+//!
+//! #[derive(Clone, Default)]
+//! struct Document {
+//!   text: String,
+//! }
+//!
+//! fn main() {
+//!   let doc = HistoryMutAppState::<Document>::get_or_insert_default();
+//!   doc.get_mut().text.push_str("Hello");
+//!
+//!   doc.undo();
+//!   assert!(doc.get_mut().text.is_empty());
+//!   doc.redo();
+//!   assert_eq!(doc.get_mut().text, "Hello");
+//! }
+//! ```
+//!
+//! ## Change subscriptions
+//! `MutAppState::subscribe` registers a callback which is called with the new value
+//! whenever a lock which mutated the state is released. The returned `Subscription`
+//! cancels the callback using `unsubscribe()`.
+//! ```rust
+//! use app_state::{MutAppState, AppStateTrait};
+//!
+//! #[derive(Default)]
+//! struct MyState {
+//!   counter: u32,
+//! }
+//!
+//! fn main() {
+//!   let state = MutAppState::<MyState>::get_or_insert_default();
+//!   let subscription = state.subscribe(|state| println!("Counter: {}", state.counter));
+//!
+//!   state.get_mut().counter += 1;
+//!   subscription.unsubscribe();
+//! }
+//! ```
+//!
+//! ## Metrics
+//! With the `metrics` feature enabled, the number of `get` calls and lock acquisitions
+//! as well as the wait and hold times of `MutAppStateLock` are recorded for every state.
+//! Use `stats()` to query them or `stats().to_prometheus()` to export them.
+//! States are identified by their kind, the type name of their value and their name,
+//! hence an `AppState<T>`, a `MutAppState<T>` and named states of the same `T`
+//! are recorded separately.
+//! ```rust
+//! # #[cfg(feature = "metrics")]
+//! use app_state::{stats, MutAppState, AppStateTrait};
+//!
+//! #[derive(Default)]
+//! struct Counter(u32);
+//!
+//! # #[cfg(feature = "metrics")]
+//! fn main() {
+//!   MutAppState::<Counter>::get_or_insert_default().get_mut().0 += 1;
+//!   MutAppState::<Counter>::get_or_insert_default_named("other");
+//!
+//!   let stats = stats();
+//!   assert_eq!(stats.get::<MutAppState<Counter>>().unwrap().lock_acquisitions, 1);
+//!   assert_eq!(stats.get_named::<MutAppState<Counter>>("other").unwrap().gets, 1);
+//!   println!("{}", stats.to_prometheus());
+//! }
+//! # #[cfg(not(feature = "metrics"))]
+//! # fn main() {}
+//! ```
+//!
+//! ## Snapshots
+//! With the `serde` feature enabled, `snapshot()` serializes all states registered using
+//! `register_serializable` or `#[app_state(serialize)]` into a single document keyed by
+//! the type names of the states. `restore()` initializes the states from such a document.
+//! ```rust
+//! # #[cfg(feature = "serde")]
+//! use app_state::{restore, snapshot, AppStateTrait, MutAppState, InitMutAppState};
+//! # #[cfg(feature = "serde")]
+//! use serde::{Deserialize, Serialize};
+//!
+//! # #[cfg(feature = "serde")]
+//! #[derive(Default, InitMutAppState, Serialize, Deserialize)]
+//! #[app_state(serialize)]
+//! struct Settings {
+//!   volume: u32,
+//! }
+//!
+//! # #[cfg(feature = "serde")]
+//! fn main() {
+//!   MutAppState::init(Settings { volume: 10 });
+//!   let saved = snapshot().unwrap();
+//!
+//!   MutAppState::<Settings>::get().get_mut().volume = 0;
+//!   restore(&saved).unwrap();
+//!   assert_eq!(MutAppState::<Settings>::get().get_mut().volume, 10);
+//! }
+//! # #[cfg(not(feature = "serde"))]
+//! # fn main() {}
+//! ```
+//!
+//! ## Persistent state
+//! A `PersistentMutAppState` is a mutable state which is loaded from a file on initialization
+//! and written back to it on lock release, periodically or on `shutdown()`,
+//! depending on its `SaveMode`. It requires the `serde` feature. States are stored as JSON
+//! by default; the `toml` and `bincode` features enable `TomlFormat` and `BincodeFormat`.
+//! ```rust
+//! # #[cfg(feature = "serde")]
+//! use app_state::{JsonFormat, PersistentMutAppState, SaveMode};
+//! # #[cfg(feature = "serde")]
+//! use serde::{Deserialize, Serialize};
+//!
+//! # #[cfg(feature = "serde")]
+//! #[derive(Default, Serialize, Deserialize)]
+//! struct Settings {
+//!   volume: u32,
+//! }
+//!
+//! # #[cfg(feature = "serde")]
+//! fn main() {
+//!   let path = std::env::temp_dir().join("app-state-persistent-example.json");
+//!   let settings =
+//!     PersistentMutAppState::<Settings>::init::<JsonFormat>(&path, SaveMode::OnRelease).unwrap();
+//!
+//!   settings.get_mut().volume = 10;
+//!   let reopened = PersistentMutAppState::<Settings>::open::<JsonFormat>(&path, SaveMode::OnRelease);
+//!   assert_eq!(reopened.unwrap().get_mut().volume, 10);
+//!   # std::fs::remove_file(&path).unwrap();
+//! }
+//! # #[cfg(not(feature = "serde"))]
+//! # fn main() {}
+//! ```
+//!
+//! ## Configuration
+//! A `ConfigState` loads a configuration file, optionally layered with environment variables,
+//! and publishes it through a `SwapAppState`. If the file is watched, every valid change
+//! atomically publishes a new version while invalid files are rejected, keeping the previous
+//! version. The `yaml` feature enables `YamlFormat`.
+//! ```rust
+//! # #[cfg(feature = "serde")]
+//! use app_state::{stateful, AppStateTrait, ConfigSource, ConfigState, JsonFormat, SwapAppState};
+//! # #[cfg(feature = "serde")]
+//! use serde::Deserialize;
+//!
+//! # #[cfg(feature = "serde")]
+//! #[derive(Deserialize)]
+//! struct Settings {
+//!   host: String,
+//!   port: u16,
+//! }
+//!
+//! # #[cfg(feature = "serde")]
+//! #[stateful]
+//! fn port(settings: SwapAppState<Settings>) -> u16 {
+//!   settings.load().port
+//! }
+//!
+//! # #[cfg(feature = "serde")]
+//! fn main() {
+//!   let path = std::env::temp_dir().join("app-state-config-lib-example.json");
+//!   std::fs::write(&path, r#"{ "host": "localhost", "port": 8080 }"#).unwrap();
+//!   std::env::set_var("APP_STATE_EXAMPLE_PORT", "9090");
+//!
+//!   let source = ConfigSource::new(&path).env_prefix("APP_STATE_EXAMPLE");
+//!   ConfigState::<Settings>::init::<JsonFormat>(source).unwrap();
+//!   assert_eq!(SwapAppState::<Settings>::get().load().host, "localhost");
+//!   assert_eq!(port(), 9090);
+//!   # std::fs::remove_file(&path).unwrap();
+//! }
+//! # #[cfg(not(feature = "serde"))]
+//! # fn main() {}
+//! ```
+
+extern crate self as app_state;
 
 mod states;
 
@@ -111,7 +523,49 @@ mod states;
 mod tests;
 
 pub use crate::states::app_state::*;
+#[cfg(feature = "tokio")]
+pub use crate::states::async_mut_app_state::*;
+#[cfg(feature = "tokio")]
+pub use crate::states::async_mut_app_state_lock::*;
+#[cfg(feature = "tokio")]
+pub use crate::states::async_rw_app_state::*;
+#[cfg(feature = "tokio")]
+pub use crate::states::async_rw_app_state_lock::*;
+#[cfg(feature = "serde")]
+pub use crate::states::config_state::*;
+pub use crate::states::error::*;
+pub use crate::states::factory::*;
+pub use crate::states::history_mut_app_state::*;
+pub use crate::states::history_mut_app_state_lock::*;
+pub use crate::states::lifecycle::{shutdown, StateLifecycle};
+pub use crate::states::lock_many::*;
+#[cfg(feature = "metrics")]
+pub use crate::states::metrics::{reset_stats, stats, StateStats, Stats, StatsKey};
 pub use crate::states::mut_app_state_lock::*;
 pub use crate::states::mutable_app_state::*;
+#[cfg(feature = "serde")]
+pub use crate::states::persist_format::*;
+#[cfg(feature = "serde")]
+pub use crate::states::persistent_mut_app_state::{PersistentMutAppState, SaveMode};
+#[cfg(feature = "serde")]
+pub use crate::states::persistent_mut_app_state_lock::*;
+pub use crate::states::poison::{default_poison_policy, set_default_poison_policy, PoisonPolicy};
+pub use crate::states::rw_app_state::*;
+pub use crate::states::rw_app_state_lock::*;
+#[cfg(feature = "serde")]
+pub use crate::states::snapshot::{
+    register_serializable, restore, snapshot, SerializableState, Snapshot,
+};
+pub use crate::states::store::*;
+pub use crate::states::subscription::Subscription;
+pub use crate::states::swap_app_state::*;
 pub use crate::states::traits::*;
+pub use crate::states::transaction::{
+    transaction, Transaction, TransactionLock, MAX_TRANSACTION_ATTEMPTS,
+};
 pub use app_state_macros::*;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::states::lifecycle::{InitLifecycle, InitPlain, InitWith};
+}
@@ -1,3 +1,4 @@
+use crate::states::store::with_current_store;
 use crate::states::traits::CreateAppState;
 use crate::AppStateTrait;
 use std::ops::Deref;
@@ -21,13 +22,55 @@ use std::sync::Arc;
 /// ```
 pub struct AppState<T: ?Sized>(Arc<T>);
 
-impl<T: 'static + Send> CreateAppState<T> for AppState<T> {
-    fn new(state: T) -> AppState<T> {
+impl<T: ?Sized + 'static + Send> CreateAppState<T> for AppState<T> {
+    fn new(state: T) -> AppState<T>
+    where
+        T: Sized,
+    {
         AppState(Arc::new(state))
     }
 }
 
-impl<T: 'static + Send + Sync> AppStateTrait<T, AppState<T>> for AppState<T> {}
+impl<T: ?Sized + 'static + Send + Sync> AppStateTrait<T, AppState<T>> for AppState<T> {}
+
+impl<T: ?Sized + 'static + Send + Sync> AppState<T> {
+    /// Initializes the state store with the given `Arc`.
+    /// In contrast to `init`, this allows initializing states
+    /// of unsized types, for example trait objects.
+    /// If the state store has already been initialized, this will overwrite the existing state.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use app_state::{AppState, AppStateTrait};
+    /// use std::sync::Arc;
+    ///
+    /// trait Storage: Send + Sync {
+    ///   fn name(&self) -> &str;
+    /// }
+    ///
+    /// struct InMemoryStorage;
+    ///
+    /// impl Storage for InMemoryStorage {
+    ///   fn name(&self) -> &str {
+    ///     "in-memory"
+    ///   }
+    /// }
+    ///
+    /// fn main() {
+    ///   AppState::<dyn Storage>::init_arc(Arc::new(InMemoryStorage));
+    ///   assert_eq!(AppState::<dyn Storage>::get().name(), "in-memory");
+    /// }
+    /// ```
+    pub fn init_arc(state: Arc<T>) {
+        with_current_store(|store| store.init_arc(state));
+    }
+
+    /// Initializes the state with the given name with the given `Arc`.
+    /// See `init_arc` and `AppStateTrait::init_named` for details.
+    pub fn init_arc_named(name: &str, state: Arc<T>) {
+        with_current_store(|store| store.init_arc_named(name, state));
+    }
+}
 
 impl<T: ?Sized> AppState<T> {
     /// Returns reference to inner `T`.
@@ -0,0 +1,69 @@
+use crate::states::traits::CreateAppState;
+use crate::{AppStateTrait, AsyncMutAppStateLock};
+use std::ops::Deref;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// A mutable app state for asynchronous code.
+/// This uses a `tokio::sync::Mutex` internally, which allows
+/// the lock to be held across `.await` points.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{AsyncMutAppState, AppStateTrait, stateful};
+///
+/// struct MyState {
+///   counter: u32,
+/// }
+///
+/// #[stateful]
+/// async fn func(state: AsyncMutAppState<MyState>) {
+///   let mut state = state.lock().await;
+///   state.counter += 1;
+/// }
+/// ```
+#[derive(Debug)]
+pub struct AsyncMutAppState<T: ?Sized>(Arc<Mutex<T>>);
+
+impl<T: 'static + Send> AsyncMutAppState<T> {
+    /// Locks the state, waiting asynchronously until the lock can be acquired.
+    /// The returned lock guard is `Send` and can be held across `.await` points.
+    pub async fn lock(&self) -> AsyncMutAppStateLock<T> {
+        AsyncMutAppStateLock::new(self).await
+    }
+}
+
+impl<T: 'static + Send> CreateAppState<T> for AsyncMutAppState<T> {
+    fn new(state: T) -> AsyncMutAppState<T> {
+        AsyncMutAppState(Arc::new(Mutex::new(state)))
+    }
+}
+
+impl<T: 'static + Send> AppStateTrait<T, AsyncMutAppState<T>> for AsyncMutAppState<T> {}
+
+impl<T: ?Sized> AsyncMutAppState<T> {
+    /// Unwraps to the internal `Arc<Mutex<T>>`
+    pub fn into_inner(self) -> Arc<Mutex<T>> {
+        self.0
+    }
+}
+
+impl<T: ?Sized> Deref for AsyncMutAppState<T> {
+    type Target = Arc<Mutex<T>>;
+
+    fn deref(&self) -> &Arc<Mutex<T>> {
+        &self.0
+    }
+}
+
+impl<T: ?Sized> Clone for AsyncMutAppState<T> {
+    fn clone(&self) -> AsyncMutAppState<T> {
+        AsyncMutAppState(self.0.clone())
+    }
+}
+
+impl<T: ?Sized> From<Arc<Mutex<T>>> for AsyncMutAppState<T> {
+    fn from(arc: Arc<Mutex<T>>) -> Self {
+        AsyncMutAppState(arc)
+    }
+}
@@ -0,0 +1,57 @@
+use crate::AsyncMutAppState;
+use std::ops::{Deref, DerefMut};
+use tokio::sync::OwnedMutexGuard;
+
+/// The lock guard for an asynchronous mutable app state.
+/// This is a wrapper around `OwnedMutexGuard`.
+/// When this guard is dropped, the lock will be released.
+/// Unlike `MutAppStateLock`, this guard does not borrow the state,
+/// is `Send` and can be held across `.await` points.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{AsyncMutAppState, AsyncMutAppStateLock, AppStateTrait, stateful};
+///
+/// struct MyState {
+///   counter: u32,
+/// }
+///
+/// #[stateful]
+/// async fn func(mut state: AsyncMutAppStateLock<MyState>) {
+///   state.counter += 1;
+/// }
+/// ```
+pub struct AsyncMutAppStateLock<T: ?Sized>(OwnedMutexGuard<T>);
+
+impl<T: 'static + Send> AsyncMutAppStateLock<T> {
+    /// Locks the given state, waiting asynchronously until the lock can be acquired.
+    pub async fn new(inner: &AsyncMutAppState<T>) -> AsyncMutAppStateLock<T> {
+        AsyncMutAppStateLock(inner.clone().into_inner().lock_owned().await)
+    }
+}
+
+impl<T: ?Sized> AsyncMutAppStateLock<T> {
+    /// Returns reference to inner `T`.
+    pub fn get_ref(&self) -> &OwnedMutexGuard<T> {
+        &self.0
+    }
+
+    /// Unwraps to the internal `OwnedMutexGuard<T>`
+    pub fn into_inner(self) -> OwnedMutexGuard<T> {
+        self.0
+    }
+}
+
+impl<T: ?Sized> Deref for AsyncMutAppStateLock<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: ?Sized> DerefMut for AsyncMutAppStateLock<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
@@ -0,0 +1,73 @@
+use crate::states::traits::CreateAppState;
+use crate::{AppStateTrait, AsyncRwAppStateReadLock, AsyncRwAppStateWriteLock};
+use std::ops::Deref;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// A reader-writer app state for asynchronous code.
+/// This uses a `tokio::sync::RwLock` internally, which allows
+/// the locks to be held across `.await` points.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{AsyncRwAppState, AppStateTrait, stateful};
+///
+/// struct MyState {
+///   counter: u32,
+/// }
+///
+/// #[stateful]
+/// async fn func(state: AsyncRwAppState<MyState>) {
+///   println!("Counter: {}", state.read().await.counter);
+///   state.write().await.counter += 1;
+/// }
+/// ```
+#[derive(Debug)]
+pub struct AsyncRwAppState<T: ?Sized>(Arc<RwLock<T>>);
+
+impl<T: 'static + Send + Sync> AsyncRwAppState<T> {
+    /// Locks the state for reading, waiting asynchronously until the lock can be acquired.
+    pub async fn read(&self) -> AsyncRwAppStateReadLock<T> {
+        AsyncRwAppStateReadLock::new(self).await
+    }
+
+    /// Locks the state for writing, waiting asynchronously until the lock can be acquired.
+    pub async fn write(&self) -> AsyncRwAppStateWriteLock<T> {
+        AsyncRwAppStateWriteLock::new(self).await
+    }
+}
+
+impl<T: 'static + Send + Sync> CreateAppState<T> for AsyncRwAppState<T> {
+    fn new(state: T) -> AsyncRwAppState<T> {
+        AsyncRwAppState(Arc::new(RwLock::new(state)))
+    }
+}
+
+impl<T: 'static + Send + Sync> AppStateTrait<T, AsyncRwAppState<T>> for AsyncRwAppState<T> {}
+
+impl<T: ?Sized> AsyncRwAppState<T> {
+    /// Unwraps to the internal `Arc<RwLock<T>>`
+    pub fn into_inner(self) -> Arc<RwLock<T>> {
+        self.0
+    }
+}
+
+impl<T: ?Sized> Deref for AsyncRwAppState<T> {
+    type Target = Arc<RwLock<T>>;
+
+    fn deref(&self) -> &Arc<RwLock<T>> {
+        &self.0
+    }
+}
+
+impl<T: ?Sized> Clone for AsyncRwAppState<T> {
+    fn clone(&self) -> AsyncRwAppState<T> {
+        AsyncRwAppState(self.0.clone())
+    }
+}
+
+impl<T: ?Sized> From<Arc<RwLock<T>>> for AsyncRwAppState<T> {
+    fn from(arc: Arc<RwLock<T>>) -> Self {
+        AsyncRwAppState(arc)
+    }
+}
@@ -0,0 +1,105 @@
+use crate::AsyncRwAppState;
+use std::ops::{Deref, DerefMut};
+use tokio::sync::{OwnedRwLockReadGuard, OwnedRwLockWriteGuard};
+
+/// The read lock guard for an asynchronous reader-writer app state.
+/// This is a wrapper around `OwnedRwLockReadGuard`.
+/// When this guard is dropped, the lock will be released.
+/// This guard is `Send` and can be held across `.await` points.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{AsyncRwAppState, AsyncRwAppStateReadLock, AppStateTrait, stateful};
+///
+/// struct MyState {
+///   counter: u32,
+/// }
+///
+/// #[stateful]
+/// async fn func(state: AsyncRwAppStateReadLock<MyState>) {
+///   println!("Counter: {}", state.counter);
+/// }
+/// ```
+pub struct AsyncRwAppStateReadLock<T: ?Sized>(OwnedRwLockReadGuard<T>);
+
+impl<T: 'static + Send + Sync> AsyncRwAppStateReadLock<T> {
+    /// Locks the given state for reading,
+    /// waiting asynchronously until the lock can be acquired.
+    pub async fn new(inner: &AsyncRwAppState<T>) -> AsyncRwAppStateReadLock<T> {
+        AsyncRwAppStateReadLock(inner.clone().into_inner().read_owned().await)
+    }
+}
+
+impl<T: ?Sized> AsyncRwAppStateReadLock<T> {
+    /// Returns reference to inner `T`.
+    pub fn get_ref(&self) -> &OwnedRwLockReadGuard<T> {
+        &self.0
+    }
+
+    /// Unwraps to the internal `OwnedRwLockReadGuard<T>`
+    pub fn into_inner(self) -> OwnedRwLockReadGuard<T> {
+        self.0
+    }
+}
+
+impl<T: ?Sized> Deref for AsyncRwAppStateReadLock<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// The write lock guard for an asynchronous reader-writer app state.
+/// This is a wrapper around `OwnedRwLockWriteGuard`.
+/// When this guard is dropped, the lock will be released.
+/// This guard is `Send` and can be held across `.await` points.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{AsyncRwAppState, AsyncRwAppStateWriteLock, AppStateTrait, stateful};
+///
+/// struct MyState {
+///   counter: u32,
+/// }
+///
+/// #[stateful]
+/// async fn func(mut state: AsyncRwAppStateWriteLock<MyState>) {
+///   state.counter += 1;
+/// }
+/// ```
+pub struct AsyncRwAppStateWriteLock<T: ?Sized>(OwnedRwLockWriteGuard<T>);
+
+impl<T: 'static + Send + Sync> AsyncRwAppStateWriteLock<T> {
+    /// Locks the given state for writing,
+    /// waiting asynchronously until the lock can be acquired.
+    pub async fn new(inner: &AsyncRwAppState<T>) -> AsyncRwAppStateWriteLock<T> {
+        AsyncRwAppStateWriteLock(inner.clone().into_inner().write_owned().await)
+    }
+}
+
+impl<T: ?Sized> AsyncRwAppStateWriteLock<T> {
+    /// Returns reference to inner `T`.
+    pub fn get_ref(&self) -> &OwnedRwLockWriteGuard<T> {
+        &self.0
+    }
+
+    /// Unwraps to the internal `OwnedRwLockWriteGuard<T>`
+    pub fn into_inner(self) -> OwnedRwLockWriteGuard<T> {
+        self.0
+    }
+}
+
+impl<T: ?Sized> Deref for AsyncRwAppStateWriteLock<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: ?Sized> DerefMut for AsyncRwAppStateWriteLock<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
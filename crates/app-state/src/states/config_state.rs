@@ -0,0 +1,411 @@
+use crate::states::metrics;
+use crate::states::store::with_current_store;
+use crate::states::traits::CreateAppState;
+use crate::{PersistFormat, StateError, StateLifecycle, SwapAppState};
+use arc_swap::ArcSwap;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Describes where a [`ConfigState`] is loaded from.
+///
+/// # Examples
+/// ```rust
+/// use app_state::ConfigSource;
+/// use std::time::Duration;
+///
+/// let source = ConfigSource::new("config.toml")
+///   .env_prefix("APP")
+///   .poll_interval(Duration::from_secs(1));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigSource {
+    path: PathBuf,
+    env_prefix: Option<String>,
+    poll_interval: Option<Duration>,
+}
+
+impl ConfigSource {
+    /// Creates a source reading the file at `path`.
+    /// The file is neither layered with environment variables nor watched.
+    pub fn new(path: impl Into<PathBuf>) -> ConfigSource {
+        ConfigSource {
+            path: path.into(),
+            env_prefix: None,
+            poll_interval: None,
+        }
+    }
+
+    /// Overrides values of the file with the environment variables starting with `prefix`.
+    ///
+    /// The variable `{prefix}_{KEY}` overrides the top-level key `key`,
+    /// nested keys are separated by double underscores, e.g. `APP_DATABASE__URL`
+    /// overrides `url` in the table `database`. The keys of the variables are lowercased,
+    /// hence keys of the file containing uppercase letters can not be overridden.
+    /// Values are parsed as JSON if possible and used as strings otherwise,
+    /// hence string values looking like numbers or booleans must be quoted,
+    /// e.g. `APP_NAME='"123"'`.
+    pub fn env_prefix(mut self, prefix: impl Into<String>) -> ConfigSource {
+        self.env_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Watches the file for changes by checking it every `interval`.
+    /// The file is polled rather than watched using notifications of the operating system.
+    /// A change is detected by the modification time and the length of the file,
+    /// and by the hash of its contents if both are unchanged.
+    pub fn poll_interval(mut self, interval: Duration) -> ConfigSource {
+        self.poll_interval = Some(interval);
+        self
+    }
+
+    /// Returns the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// An event emitted by a [`ConfigState`] when its file has been reloaded.
+#[derive(Debug)]
+pub enum ConfigEvent<T> {
+    /// A new version of the configuration has been published.
+    Reloaded(Arc<T>),
+    /// The file could not be loaded. The previous version is kept.
+    Rejected(StateError),
+}
+
+type Listener<T> = Box<dyn Fn(&ConfigEvent<T>) + Send + Sync>;
+
+/// The modification time, length and content hash of a file, used to detect changes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Fingerprint {
+    modified: SystemTime,
+    len: u64,
+    hash: u64,
+}
+
+struct Config<T: 'static + Send + Sync> {
+    state: SwapAppState<T>,
+    source: ConfigSource,
+    parse: fn(&[u8]) -> Result<Value, String>,
+    fingerprint: Mutex<Option<Fingerprint>>,
+    listeners: Mutex<Vec<Listener<T>>>,
+    last_error: Mutex<Option<StateError>>,
+    stopped: AtomicBool,
+}
+
+impl<T: 'static + Send + Sync + DeserializeOwned> Config<T> {
+    fn reload(&self) -> Result<Arc<T>, StateError> {
+        *self
+            .fingerprint
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = fingerprint(&self.source.path);
+
+        let event = match read::<T>(&self.source, self.parse) {
+            Ok(value) => {
+                let value = Arc::new(value);
+                let swap: &ArcSwap<T> = &self.state;
+                swap.store(value.clone());
+                ConfigEvent::Reloaded(value)
+            }
+            Err(err) => {
+                #[cfg(feature = "log")]
+                log::error!("Rejected configuration: {}", err);
+
+                *self
+                    .last_error
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner) = Some(err.clone());
+                ConfigEvent::Rejected(err)
+            }
+        };
+
+        for listener in self
+            .listeners
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+        {
+            listener(&event);
+        }
+
+        match event {
+            ConfigEvent::Reloaded(value) => Ok(value),
+            ConfigEvent::Rejected(err) => Err(err),
+        }
+    }
+
+    /// Reloads the file if it has changed since it has last been read.
+    fn reload_if_changed(&self) {
+        let previous = *self
+            .fingerprint
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let metadata = fs::metadata(&self.source.path)
+            .ok()
+            .and_then(|metadata| Some((metadata.modified().ok()?, metadata.len())));
+
+        let changed = match (previous, metadata) {
+            // Edits keeping the length within the resolution of the
+            // modification time can only be detected by the contents
+            (Some(previous), Some((modified, len)))
+                if previous.modified == modified && previous.len == len =>
+            {
+                hash_contents(&self.source.path) != Some(previous.hash)
+            }
+            (None, None) => false,
+            _ => true,
+        };
+
+        if changed {
+            let _ = self.reload();
+        }
+    }
+}
+
+/// Reads the file of `source`, layered with its environment variables.
+fn read<T: DeserializeOwned>(
+    source: &ConfigSource,
+    parse: fn(&[u8]) -> Result<Value, String>,
+) -> Result<T, StateError> {
+    let bytes = fs::read(&source.path).map_err(|err| StateError::Persistence {
+        path: source.path.clone(),
+        message: err.to_string(),
+    })?;
+
+    let mut value = parse(&bytes).map_err(StateError::deserialization::<T>)?;
+    if let Some(prefix) = &source.env_prefix {
+        apply_env(&mut value, prefix, std::env::vars());
+    }
+
+    serde_json::from_value(value).map_err(|err| StateError::deserialization::<T>(err.to_string()))
+}
+
+fn fingerprint(path: &Path) -> Option<Fingerprint> {
+    let metadata = fs::metadata(path).ok()?;
+    Some(Fingerprint {
+        modified: metadata.modified().ok()?,
+        len: metadata.len(),
+        hash: hash_contents(path)?,
+    })
+}
+
+fn hash_contents(path: &Path) -> Option<u64> {
+    let mut hasher = DefaultHasher::new();
+    fs::read(path).ok()?.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+/// Overrides the values in `value` with the variables in `vars` starting with `prefix`.
+/// Keys are lowercased and split into nested keys at double underscores.
+/// Values are parsed as JSON, falling back to strings if they are not valid JSON.
+fn apply_env(value: &mut Value, prefix: &str, vars: impl Iterator<Item = (String, String)>) {
+    let prefix = format!("{}_", prefix);
+    for (key, var) in vars {
+        let Some(key) = key.strip_prefix(&prefix) else {
+            continue;
+        };
+
+        let key = key.to_lowercase();
+        let mut target = &mut *value;
+        for segment in key.split("__") {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+
+            target = target
+                .as_object_mut()
+                .unwrap()
+                .entry(segment)
+                .or_insert(Value::Null);
+        }
+
+        *target = serde_json::from_str(&var).unwrap_or(Value::String(var));
+    }
+}
+
+/// A configuration state which is loaded from a file and reloaded when the file changes.
+///
+/// The file is parsed using a [`PersistFormat`], e.g. [`JsonFormat`](crate::JsonFormat),
+/// and may be layered with environment variables, see [`ConfigSource::env_prefix`].
+/// Formats which are not self-describing, like `BincodeFormat`, are not supported.
+///
+/// Every version of the configuration is published atomically through a
+/// [`SwapAppState`], hence readers never observe a partially updated configuration.
+/// If the file is invalid when it is reloaded, a [`ConfigEvent::Rejected`] event is
+/// emitted and the previous version is kept.
+///
+/// [`ConfigState::init`] initializes both the `ConfigState<T>` and the underlying
+/// `SwapAppState<T>` in the state store, so functions only reading the configuration
+/// may simply inject a `SwapAppState<T>`.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{ConfigSource, ConfigState, JsonFormat, SwapAppState, AppStateTrait};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Settings {
+///   port: u16,
+/// }
+///
+/// fn main() {
+///   let path = std::env::temp_dir().join("app-state-config-example.json");
+///   std::fs::write(&path, r#"{ "port": 8080 }"#).unwrap();
+///
+///   ConfigState::<Settings>::init::<JsonFormat>(ConfigSource::new(&path)).unwrap();
+///   assert_eq!(SwapAppState::<Settings>::get().load().port, 8080);
+///
+///   std::fs::write(&path, r#"{ "port": 9090 }"#).unwrap();
+///   ConfigState::<Settings>::get().reload().unwrap();
+///   assert_eq!(SwapAppState::<Settings>::get().load().port, 9090);
+///   # std::fs::remove_file(&path).unwrap();
+/// }
+/// ```
+pub struct ConfigState<T: 'static + Send + Sync>(Arc<Config<T>>);
+
+impl<T: 'static + Send + Sync + DeserializeOwned> ConfigState<T> {
+    /// Loads the configuration without adding it to the state store.
+    /// If the source has a poll interval, the file is watched from a background thread
+    /// until all handles to the state have been dropped or [`shutdown`](crate::shutdown)
+    /// has been called.
+    /// Returns `Err` if the file could not be read or deserialized.
+    pub fn open<F: PersistFormat>(source: ConfigSource) -> Result<ConfigState<T>, StateError> {
+        let parse = F::from_bytes::<Value>;
+        let fingerprint = fingerprint(&source.path);
+        let value = read::<T>(&source, parse)?;
+
+        let poll_interval = source.poll_interval;
+        let config = Arc::new(Config {
+            state: SwapAppState::new(value),
+            source,
+            parse,
+            fingerprint: Mutex::new(fingerprint),
+            listeners: Mutex::new(Vec::new()),
+            last_error: Mutex::new(None),
+            stopped: AtomicBool::new(false),
+        });
+
+        if let Some(interval) = poll_interval {
+            spawn_watcher(Arc::downgrade(&config), interval);
+        }
+
+        Ok(ConfigState(config))
+    }
+
+    /// Loads the configuration and initializes it in the state store,
+    /// along with the underlying `SwapAppState<T>`.
+    /// If the state has already been initialized, this will overwrite the existing state.
+    /// See [`ConfigState::open`] for details.
+    pub fn init<F: PersistFormat>(source: ConfigSource) -> Result<ConfigState<T>, StateError> {
+        let state = Self::open::<F>(source)?;
+        with_current_store(|store| {
+            store.insert_state(None, state.0.state.clone());
+            store.insert_with_lifecycle(state.clone());
+        });
+
+        Ok(state)
+    }
+
+    /// Reloads the file, regardless of whether it has changed.
+    /// Publishes and returns the new version if the file is valid.
+    /// Otherwise, the previous version is kept and the error is returned.
+    /// Emits a [`ConfigEvent`] in both cases.
+    pub fn reload(&self) -> Result<Arc<T>, StateError> {
+        self.0.reload()
+    }
+}
+
+impl<T: 'static + Send + Sync> ConfigState<T> {
+    /// Returns the state from the state store.
+    /// If the state has not been initialized, this will panic.
+    pub fn get() -> ConfigState<T> {
+        metrics::record_get::<ConfigState<T>, T>(None);
+        with_current_store(|store| store.get())
+    }
+
+    /// Returns the state from the state store.
+    /// If the state has not been initialized, this will return `Err`.
+    pub fn try_get() -> Result<ConfigState<T>, StateError> {
+        metrics::record_get::<ConfigState<T>, T>(None);
+        with_current_store(|store| store.try_get())
+    }
+
+    /// Returns a snapshot of the current version of the configuration.
+    pub fn load(&self) -> Arc<T> {
+        self.0.state.load()
+    }
+
+    /// Returns the `SwapAppState<T>` the versions of the configuration are published to.
+    pub fn state(&self) -> SwapAppState<T> {
+        self.0.state.clone()
+    }
+
+    /// Returns the source the configuration is loaded from.
+    pub fn source(&self) -> &ConfigSource {
+        &self.0.source
+    }
+
+    /// Calls `listener` whenever the file has been reloaded.
+    /// Listeners are called on the thread reloading the file,
+    /// which is a background thread if the file is watched.
+    pub fn subscribe<F>(&self, listener: F)
+    where
+        F: Fn(&ConfigEvent<T>) + Send + Sync + 'static,
+    {
+        self.0
+            .listeners
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(listener));
+    }
+
+    /// Returns the last error which occurred while reloading the file and resets it.
+    pub fn last_error(&self) -> Option<StateError> {
+        self.0
+            .last_error
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+    }
+}
+
+impl<T: 'static + Send + Sync> StateLifecycle for ConfigState<T> {
+    fn on_shutdown(&self) {
+        self.0.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+impl<T: 'static + Send + Sync> Clone for ConfigState<T> {
+    fn clone(&self) -> Self {
+        ConfigState(self.0.clone())
+    }
+}
+
+fn spawn_watcher<T>(config: Weak<Config<T>>, interval: Duration)
+where
+    T: 'static + Send + Sync + DeserializeOwned,
+{
+    let res = thread::Builder::new()
+        .name("app-state-config".to_string())
+        .spawn(move || loop {
+            thread::sleep(interval);
+            match config.upgrade() {
+                Some(config) if !config.stopped.load(Ordering::Relaxed) => {
+                    config.reload_if_changed()
+                }
+                _ => break,
+            }
+        });
+
+    if let Err(_err) = res {
+        #[cfg(feature = "log")]
+        log::error!("Could not start watching configuration: {}", _err);
+    }
+}
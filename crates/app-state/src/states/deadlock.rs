@@ -0,0 +1,274 @@
+//! Detection of deadlocks caused by locking mutable states.
+//!
+//! With the `deadlock-detection` feature enabled, every `MutAppStateLock` records
+//! which states the current thread holds, and every state locked while holding
+//! another one records the order the two states have been locked in.
+//! Locking a state which is already held by the current thread or locking
+//! two states in the opposite order of a previously recorded order panics,
+//! even if no other thread is currently waiting for the held states.
+//! Without the feature, the functions in this module do nothing.
+
+use std::fmt::{Display, Formatter};
+use std::panic::Location;
+
+/// The place a state is locked at.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(not(feature = "deadlock-detection"), allow(dead_code))]
+pub(crate) enum LockSite {
+    /// A function the lock has been injected into using `stateful`.
+    Function(&'static str),
+    /// The location of the caller locking the state.
+    Caller(&'static Location<'static>),
+}
+
+impl LockSite {
+    #[track_caller]
+    pub(crate) fn caller() -> LockSite {
+        LockSite::Caller(Location::caller())
+    }
+}
+
+impl Display for LockSite {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockSite::Function(function) => write!(f, "{function}"),
+            LockSite::Caller(location) => write!(f, "{location}"),
+        }
+    }
+}
+
+#[cfg(feature = "deadlock-detection")]
+mod detection {
+    use super::LockSite;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+
+    /// A state held by the current thread.
+    #[derive(Clone, Copy)]
+    struct Held {
+        state: usize,
+        type_name: &'static str,
+        site: LockSite,
+    }
+
+    /// A state which has been locked while holding another state.
+    struct Edge {
+        to: usize,
+        from_site: LockSite,
+        to_site: LockSite,
+    }
+
+    /// The recorded lock order, keyed by the state which has been locked first.
+    #[derive(Default)]
+    struct Order {
+        edges: HashMap<usize, Vec<Edge>>,
+        type_names: HashMap<usize, &'static str>,
+    }
+
+    thread_local! {
+        static HELD: RefCell<Vec<Held>> = const { RefCell::new(Vec::new()) };
+    }
+
+    static ORDER: OnceLock<Mutex<Order>> = OnceLock::new();
+
+    fn order() -> MutexGuard<'static, Order> {
+        // The order is never left in an inconsistent state
+        ORDER
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    impl Order {
+        /// Returns the recorded path from `from` to `to`, if any.
+        fn path(&self, from: usize, to: usize) -> Option<Vec<&Edge>> {
+            let mut visited = vec![from];
+            let mut stack = vec![(from, Vec::new())];
+
+            while let Some((state, path)) = stack.pop() {
+                for edge in self.edges.get(&state).into_iter().flatten() {
+                    let mut path = path.clone();
+                    path.push(edge);
+                    if edge.to == to {
+                        return Some(path);
+                    }
+
+                    if !visited.contains(&edge.to) {
+                        visited.push(edge.to);
+                        stack.push((edge.to, path));
+                    }
+                }
+            }
+
+            None
+        }
+
+        fn type_name(&self, state: usize) -> &'static str {
+            self.type_names.get(&state).copied().unwrap_or("<unknown>")
+        }
+
+        /// Records that `next` is locked while holding `held`.
+        /// Returns a description of the inversion if `held` has been locked while holding `next` before.
+        fn record(&mut self, held: &Held, next: &Held) -> Option<String> {
+            if self
+                .edges
+                .get(&held.state)
+                .is_some_and(|edges| edges.iter().any(|e| e.to == next.state))
+            {
+                return None;
+            }
+
+            if let Some(path) = self.path(next.state, held.state) {
+                let mut from = next.state;
+                let previous = path
+                    .iter()
+                    .map(|edge| {
+                        let description = format!(
+                            "{} has been locked by {} while holding {}, which was locked by {}",
+                            self.type_name(edge.to),
+                            edge.to_site,
+                            self.type_name(from),
+                            edge.from_site
+                        );
+                        from = edge.to;
+                        description
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                return Some(format!(
+                    "Deadlock detected: {} tried to lock state {} while holding state {}, which was locked by {}, \
+                    but the states have been locked in the opposite order before: {previous}",
+                    next.site, next.type_name, held.type_name, held.site
+                ));
+            }
+
+            self.type_names.insert(held.state, held.type_name);
+            self.type_names.insert(next.state, next.type_name);
+            self.edges.entry(held.state).or_default().push(Edge {
+                to: next.state,
+                from_site: held.site,
+                to_site: next.site,
+            });
+
+            None
+        }
+    }
+
+    pub(crate) struct Waiting(Held);
+
+    impl Waiting {
+        pub(crate) fn acquired(self) -> HeldLock {
+            HELD.with(|held| held.borrow_mut().push(self.0));
+            HeldLock {
+                state: self.0.state,
+            }
+        }
+    }
+
+    pub(crate) fn wait_for(
+        state: usize,
+        type_name: &'static str,
+        site: LockSite,
+        blocking: bool,
+    ) -> Waiting {
+        let next = Held {
+            state,
+            type_name,
+            site,
+        };
+
+        // Locking without blocking can not deadlock, hence it does not define an order
+        if !blocking {
+            return Waiting(next);
+        }
+
+        let held = HELD.with(|held| held.borrow().clone());
+        if let Some(holder) = held.iter().find(|h| h.state == state) {
+            panic!(
+                "Deadlock detected: {site} tried to lock state {type_name}, \
+                which is already locked by {} on the same thread",
+                holder.site
+            );
+        }
+
+        if !held.is_empty() {
+            let mut order = order();
+            if let Some(message) = held.iter().find_map(|h| order.record(h, &next)) {
+                drop(order);
+                panic!("{}", message);
+            }
+        }
+
+        Waiting(next)
+    }
+
+    /// Removes the recorded lock order of a dropped state,
+    /// as its address may be reused by another state.
+    pub(crate) fn forget(state: usize) {
+        let Some(order) = ORDER.get() else {
+            return;
+        };
+
+        let mut order = order.lock().unwrap_or_else(PoisonError::into_inner);
+        if order.type_names.remove(&state).is_some() {
+            order.edges.remove(&state);
+            for edges in order.edges.values_mut() {
+                edges.retain(|e| e.to != state);
+            }
+        }
+    }
+
+    /// A state locked by the current thread.
+    pub(crate) struct HeldLock {
+        state: usize,
+    }
+
+    impl Drop for HeldLock {
+        fn drop(&mut self) {
+            // The thread local may already be destroyed if the lock is dropped during thread exit
+            let _ = HELD.try_with(|held| {
+                let mut held = held.borrow_mut();
+                if let Some(index) = held.iter().rposition(|h| h.state == self.state) {
+                    held.remove(index);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(not(feature = "deadlock-detection"))]
+mod detection {
+    use super::LockSite;
+
+    pub(crate) struct Waiting;
+
+    impl Waiting {
+        pub(crate) fn acquired(self) -> HeldLock {
+            HeldLock
+        }
+    }
+
+    pub(crate) fn wait_for(_: usize, _: &'static str, _: LockSite, _: bool) -> Waiting {
+        Waiting
+    }
+
+    pub(crate) fn forget(_: usize) {}
+
+    pub(crate) struct HeldLock;
+}
+
+pub(crate) use detection::{forget, HeldLock};
+
+/// Checks whether the current thread may wait for `state` to be unlocked.
+/// If `blocking` is true and the state is already held by the current thread
+/// or has been locked before a state held by the current thread, this panics.
+/// Call `acquired` on the result once the state has been locked.
+pub(crate) fn wait_for<T: ?Sized>(
+    state: usize,
+    site: LockSite,
+    blocking: bool,
+) -> detection::Waiting {
+    detection::wait_for(state, std::any::type_name::<T>(), site, blocking)
+}
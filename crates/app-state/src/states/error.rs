@@ -0,0 +1,270 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
+/// The error returned by all fallible operations on app states.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{AppState, AppStateTrait, StateError};
+///
+/// struct MyState;
+///
+/// fn main() {
+///   match AppState::<MyState>::try_get() {
+///     Ok(_) => println!("Found state"),
+///     Err(StateError::NotFound { type_name, .. }) => println!("{type_name} is missing"),
+///     Err(StateError::StoreNotInitialized) => println!("No states are initialized"),
+///     Err(err) => panic!("{err}"),
+///   }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StateError {
+    /// No state has been initialized in the state store yet.
+    StoreNotInitialized,
+    /// The requested state has not been initialized.
+    NotFound {
+        /// The type name of the requested state.
+        type_name: &'static str,
+        /// The name of the requested state, if it is a named state.
+        name: Option<String>,
+    },
+    /// The stored state could not be cast to the requested type.
+    TypeMismatch {
+        /// The type name of the requested state.
+        type_name: &'static str,
+    },
+    /// The state has been poisoned by a panic while it was locked.
+    Poisoned {
+        /// The type name of the poisoned state.
+        type_name: &'static str,
+    },
+    /// The state is currently locked by someone else.
+    WouldBlock {
+        /// The type name of the locked state.
+        type_name: &'static str,
+    },
+    /// The state could not be locked in time.
+    LockTimeout {
+        /// The type name of the state which could not be locked.
+        type_name: &'static str,
+    },
+    /// The initializer of the state (indirectly) requires the state itself.
+    InitializationCycle {
+        /// The type name of the state.
+        type_name: &'static str,
+        /// The name of the state, if it is a named state.
+        name: Option<String>,
+    },
+    /// A state provided by a factory depends on a state
+    /// which is neither initialized nor provided by a factory.
+    MissingDependency {
+        /// The type name of the state provided by the factory.
+        type_name: &'static str,
+        /// The type name of the missing dependency.
+        dependency: &'static str,
+    },
+    /// The dependencies of the states provided by factories form a cycle.
+    DependencyCycle {
+        /// The type names of the states in the cycle,
+        /// starting and ending with the same state.
+        type_names: Vec<&'static str>,
+    },
+    /// The state could not be serialized.
+    Serialization {
+        /// The type name of the state.
+        type_name: &'static str,
+        /// The error reported by the serializer.
+        message: String,
+    },
+    /// The state could not be deserialized.
+    Deserialization {
+        /// The type name of the state.
+        type_name: &'static str,
+        /// The error reported by the deserializer.
+        message: String,
+    },
+    /// A snapshot contains a state which has not been registered as serializable.
+    UnknownState {
+        /// The type name of the state.
+        type_name: String,
+    },
+    /// A type has been registered as more than one kind of serializable state,
+    /// which can not be told apart in a snapshot.
+    SerializationConflict {
+        /// The type name of the state values.
+        type_name: &'static str,
+    },
+    /// The file of a persistent state could not be read or written.
+    Persistence {
+        /// The path of the file.
+        path: PathBuf,
+        /// The error reported by the file system.
+        message: String,
+    },
+    /// The state has been changed since the expected version.
+    VersionMismatch {
+        /// The type name of the state.
+        type_name: &'static str,
+        /// The version the state was expected to have.
+        expected: u64,
+        /// The current version of the state.
+        actual: u64,
+    },
+    /// A transaction has been aborted as its states kept being changed by someone else.
+    TransactionConflict {
+        /// The number of times the transaction has been attempted.
+        attempts: usize,
+    },
+}
+
+impl StateError {
+    pub(crate) fn not_found<T: ?Sized>(name: Option<&str>) -> StateError {
+        StateError::NotFound {
+            type_name: std::any::type_name::<T>(),
+            name: name.map(str::to_string),
+        }
+    }
+
+    pub(crate) fn type_mismatch<T: ?Sized>() -> StateError {
+        StateError::TypeMismatch {
+            type_name: std::any::type_name::<T>(),
+        }
+    }
+
+    pub(crate) fn initialization_cycle<T: ?Sized>(name: Option<&str>) -> StateError {
+        StateError::InitializationCycle {
+            type_name: std::any::type_name::<T>(),
+            name: name.map(str::to_string),
+        }
+    }
+
+    pub(crate) fn poisoned<T: ?Sized>() -> StateError {
+        StateError::Poisoned {
+            type_name: std::any::type_name::<T>(),
+        }
+    }
+
+    pub(crate) fn would_block<T: ?Sized>() -> StateError {
+        StateError::WouldBlock {
+            type_name: std::any::type_name::<T>(),
+        }
+    }
+
+    pub(crate) fn lock_timeout<T: ?Sized>() -> StateError {
+        StateError::LockTimeout {
+            type_name: std::any::type_name::<T>(),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn serialization<T: ?Sized>(message: String) -> StateError {
+        StateError::Serialization {
+            type_name: std::any::type_name::<T>(),
+            message,
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn deserialization<T: ?Sized>(message: String) -> StateError {
+        StateError::Deserialization {
+            type_name: std::any::type_name::<T>(),
+            message,
+        }
+    }
+
+    pub(crate) fn version_mismatch<T: ?Sized>(expected: u64, actual: u64) -> StateError {
+        StateError::VersionMismatch {
+            type_name: std::any::type_name::<T>(),
+            expected,
+            actual,
+        }
+    }
+}
+
+impl Display for StateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StateError::StoreNotInitialized => {
+                write!(f, "The state store has not yet been initialized")
+            }
+            StateError::NotFound {
+                type_name,
+                name: None,
+            } => write!(f, "Could not find requested state {type_name}"),
+            StateError::NotFound {
+                type_name,
+                name: Some(name),
+            } => write!(
+                f,
+                "Could not find requested state {type_name} named '{name}'"
+            ),
+            StateError::TypeMismatch { type_name } => {
+                write!(f, "Could not cast to requested state {type_name}")
+            }
+            StateError::Poisoned { type_name } => write!(f, "The state {type_name} is poisoned"),
+            StateError::WouldBlock { type_name } => {
+                write!(f, "The state {type_name} is currently locked")
+            }
+            StateError::LockTimeout { type_name } => {
+                write!(f, "Timed out while locking state {type_name}")
+            }
+            StateError::InitializationCycle {
+                type_name,
+                name: None,
+            } => write!(f, "Cyclic dependency while initializing state {type_name}"),
+            StateError::InitializationCycle {
+                type_name,
+                name: Some(name),
+            } => write!(
+                f,
+                "Cyclic dependency while initializing state {type_name} named '{name}'"
+            ),
+            StateError::MissingDependency {
+                type_name,
+                dependency,
+            } => write!(
+                f,
+                "State {type_name} depends on {dependency}, which is neither initialized nor provided by a factory"
+            ),
+            StateError::DependencyCycle { type_names } => write!(
+                f,
+                "Cyclic dependency between states {}",
+                type_names.join(" -> ")
+            ),
+            StateError::Serialization { type_name, message } => {
+                write!(f, "Could not serialize state {type_name}: {message}")
+            }
+            StateError::Deserialization { type_name, message } => {
+                write!(f, "Could not deserialize state {type_name}: {message}")
+            }
+            StateError::UnknownState { type_name } => write!(
+                f,
+                "The state {type_name} has not been registered as serializable"
+            ),
+            StateError::SerializationConflict { type_name } => write!(
+                f,
+                "The type {type_name} has been registered as more than one kind of serializable state"
+            ),
+            StateError::Persistence { path, message } => {
+                write!(f, "Could not access {}: {message}", path.display())
+            }
+            StateError::VersionMismatch {
+                type_name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Expected version {expected} of state {type_name}, but found version {actual}"
+            ),
+            StateError::TransactionConflict { attempts } => write!(
+                f,
+                "The transaction has been aborted after {attempts} attempts due to conflicting changes"
+            ),
+        }
+    }
+}
+
+impl Error for StateError {}
@@ -0,0 +1,238 @@
+use crate::states::store::with_current_store;
+use crate::states::traits::CreateAppState;
+use crate::{StateError, StateStore};
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A state required by a [`StateFactory`].
+#[derive(Debug, Clone, Copy)]
+pub struct Dependency {
+    type_id: TypeId,
+    type_name: &'static str,
+    exists: fn(&StateStore) -> bool,
+}
+
+impl Dependency {
+    /// Creates a dependency on the state `U`,
+    /// for example `AppState<Config>` or `MutAppState<Db>`.
+    pub fn of<U: 'static + Clone>() -> Dependency {
+        Dependency {
+            type_id: TypeId::of::<U>(),
+            type_name: std::any::type_name::<U>(),
+            exists: |store| store.try_get::<U>().is_ok(),
+        }
+    }
+
+    /// Returns the type name of the required state.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+}
+
+/// Builds a state from the states it depends on.
+///
+/// Factories are registered using [`register_factory`] and built using
+/// [`initialize_all`], which initializes all dependencies of a state before
+/// the state itself. The `InitAppState`, `InitMutAppState` and `InitRwAppState`
+/// derive macros register a factory if the `app_state(depends_on(...))`
+/// attribute is present.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{AppState, Dependency, StateFactory, StateStore};
+///
+/// struct Config {
+///   url: String,
+/// }
+///
+/// struct Client {
+///   url: String,
+/// }
+///
+/// fn main() {
+///   let store = StateStore::new();
+///   store.register_factory(StateFactory::new::<AppState<_>, _>(
+///     vec![Dependency::of::<AppState<Config>>()],
+///     |store| Client {
+///       url: store.get::<AppState<Config>>().url.clone(),
+///     },
+///   ));
+///   store.register_factory(StateFactory::new::<AppState<_>, _>(vec![], |_| Config {
+///     url: "localhost".to_string(),
+///   }));
+///
+///   store.initialize_all().unwrap();
+///   assert_eq!(store.get::<AppState<Client>>().url, "localhost");
+/// }
+/// ```
+pub struct StateFactory {
+    state: Dependency,
+    dependencies: Vec<Dependency>,
+    init: Box<dyn Fn(&StateStore) + Send + Sync>,
+}
+
+impl StateFactory {
+    /// Creates a factory for the state `U`.
+    /// `build` is called with the store the state is initialized in,
+    /// after all `dependencies` have been initialized in that store.
+    pub fn new<U, T>(dependencies: Vec<Dependency>, build: fn(&StateStore) -> T) -> StateFactory
+    where
+        T: 'static + Send,
+        U: 'static + CreateAppState<T> + Clone + Send,
+    {
+        StateFactory {
+            state: Dependency::of::<U>(),
+            dependencies,
+            init: Box::new(move |store| {
+                store.get_or_insert_with::<U, T, _>(|| build(store));
+            }),
+        }
+    }
+
+    /// Returns the type name of the state built by this factory.
+    pub fn type_name(&self) -> &'static str {
+        self.state.type_name
+    }
+
+    /// Returns the states this factory depends on.
+    pub fn dependencies(&self) -> &[Dependency] {
+        &self.dependencies
+    }
+
+    pub(crate) fn init(&self, store: &StateStore) {
+        (self.init)(store);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Unvisited,
+    Visiting,
+    Done,
+}
+
+/// Sorts `factories` such that every factory comes after the factories of its dependencies.
+/// If multiple factories provide the same state, the last one is used.
+pub(crate) fn initialization_order(
+    store: &StateStore,
+    factories: &[Arc<StateFactory>],
+) -> Result<Vec<Arc<StateFactory>>, StateError> {
+    let providers = factories
+        .iter()
+        .enumerate()
+        .map(|(i, factory)| (factory.state.type_id, i))
+        .collect::<HashMap<_, _>>();
+
+    let mut marks = vec![Mark::Unvisited; factories.len()];
+    let mut path = Vec::new();
+    let mut order = Vec::new();
+    for i in 0..factories.len() {
+        if providers.get(&factories[i].state.type_id) == Some(&i) {
+            visit(
+                store, factories, &providers, i, &mut marks, &mut path, &mut order,
+            )?;
+        }
+    }
+
+    Ok(order.into_iter().map(|i| factories[i].clone()).collect())
+}
+
+fn visit(
+    store: &StateStore,
+    factories: &[Arc<StateFactory>],
+    providers: &HashMap<TypeId, usize>,
+    i: usize,
+    marks: &mut [Mark],
+    path: &mut Vec<usize>,
+    order: &mut Vec<usize>,
+) -> Result<(), StateError> {
+    match marks[i] {
+        Mark::Done => return Ok(()),
+        Mark::Visiting => {
+            let start = path.iter().position(|&p| p == i).unwrap_or_default();
+            return Err(StateError::DependencyCycle {
+                type_names: path[start..]
+                    .iter()
+                    .chain([&i])
+                    .map(|&p| factories[p].type_name())
+                    .collect(),
+            });
+        }
+        Mark::Unvisited => {}
+    }
+
+    marks[i] = Mark::Visiting;
+    path.push(i);
+    for dependency in &factories[i].dependencies {
+        match providers.get(&dependency.type_id) {
+            Some(&provider) => visit(store, factories, providers, provider, marks, path, order)?,
+            None if (dependency.exists)(store) => {}
+            None => {
+                return Err(StateError::MissingDependency {
+                    type_name: factories[i].type_name(),
+                    dependency: dependency.type_name,
+                })
+            }
+        }
+    }
+    path.pop();
+    marks[i] = Mark::Done;
+    order.push(i);
+
+    Ok(())
+}
+
+/// Registers a factory in the state store.
+/// The factory is built by the next call to [`initialize_all`].
+///
+/// If the current thread has been isolated using [`StateStore::isolated`],
+/// the factory is only registered in the isolated store.
+pub fn register_factory(factory: StateFactory) {
+    with_current_store(|store| store.register_factory(factory));
+}
+
+/// Initializes all states provided by registered factories.
+/// The dependencies of every state are initialized before the state itself
+/// and states which have already been initialized are left unchanged.
+///
+/// Before any state is built, this checks that the dependencies form no cycle
+/// and that every dependency is either initialized or provided by a factory.
+/// Otherwise, [`StateError::DependencyCycle`] or [`StateError::MissingDependency`]
+/// is returned.
+///
+/// # Examples
+/// ```no_run
+/// use app_state::{AppState, AppStateTrait, InitAppState, initialize_all};
+///
+/// #[derive(InitAppState)]
+/// #[app_state(depends_on())]
+/// struct Config {
+///   url: String,
+/// }
+///
+/// impl Config {
+///   fn new() -> Self {
+///     Config { url: "localhost".to_string() }
+///   }
+/// }
+///
+/// #[derive(InitAppState)]
+/// #[app_state(depends_on(Config))]
+/// struct Client {
+///   url: String,
+/// }
+///
+/// impl Client {
+///   fn new(config: AppState<Config>) -> Self {
+///     Client { url: config.url.clone() }
+///   }
+/// }
+///
+/// fn main() {
+///   initialize_all().expect("Failed to initialize states");
+/// }
+/// ```
+pub fn initialize_all() -> Result<(), StateError> {
+    with_current_store(|store| store.initialize_all())
+}
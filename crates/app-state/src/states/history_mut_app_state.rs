@@ -0,0 +1,248 @@
+use crate::states::metrics::StatsKey;
+use crate::states::traits::CreateAppState;
+use crate::{AppStateTrait, HistoryMutAppStateLock, MutAppState, MutAppStateLock};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// The number of undo steps kept by a [`HistoryMutAppState`] by default.
+pub const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+/// The recorded versions of a [`HistoryMutAppState`].
+struct Versions<T> {
+    undo: VecDeque<T>,
+    redo: Vec<T>,
+    limit: usize,
+    group_depth: usize,
+    /// The version before the first change made in the current group.
+    group_start: Option<T>,
+}
+
+impl<T> Versions<T> {
+    fn push_undo(&mut self, version: T) {
+        if self.limit == 0 {
+            return;
+        }
+
+        if self.undo.len() >= self.limit {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(version);
+    }
+
+    /// Records `before`, the version before a committed change.
+    fn commit(&mut self, before: T) {
+        self.redo.clear();
+        if self.group_depth == 0 {
+            self.push_undo(before);
+        } else if self.group_start.is_none() {
+            self.group_start = Some(before);
+        }
+    }
+}
+
+pub(crate) struct History<T: 'static + Send> {
+    state: MutAppState<T>,
+    versions: Mutex<Versions<T>>,
+}
+
+impl<T: 'static + Send> History<T> {
+    pub(crate) fn state(&self) -> &MutAppState<T> {
+        &self.state
+    }
+
+    fn versions(&self) -> MutexGuard<'_, Versions<T>> {
+        self.versions.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn commit(&self, before: T) {
+        self.versions().commit(before);
+    }
+}
+
+/// A mutable app state which records the history of its versions
+/// in order to undo and redo changes.
+///
+/// Every [`HistoryMutAppStateLock`] which mutably dereferenced the state records
+/// the version before its changes once it is released. Several changes can be
+/// grouped into a single undo step using [`HistoryMutAppState::begin_group`].
+/// At most [`DEFAULT_HISTORY_LIMIT`] undo steps are kept, unless a different
+/// limit is set using [`HistoryMutAppState::set_history_limit`].
+/// Making a new change discards all versions which could be redone.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{HistoryMutAppState, AppStateTrait};
+///
+/// #[derive(Clone)]
+/// struct Document {
+///   text: String,
+/// }
+///
+/// fn main() {
+///   HistoryMutAppState::init(Document { text: String::new() });
+///   let doc = HistoryMutAppState::<Document>::get();
+///
+///   doc.get_mut().text.push_str("Hello");
+///   doc.get_mut().text.push_str(", world");
+///   assert_eq!(doc.history_len(), 2);
+///
+///   doc.undo();
+///   assert_eq!(doc.get_mut().text, "Hello");
+///
+///   doc.redo();
+///   assert_eq!(doc.get_mut().text, "Hello, world");
+/// }
+/// ```
+pub struct HistoryMutAppState<T: 'static + Send>(Arc<History<T>>);
+
+impl<T: 'static + Clone + Send> HistoryMutAppState<T> {
+    /// Locks the state. Changes made through the returned lock are recorded
+    /// as a single undo step once it is released.
+    /// Poisoned states are handled like in [`MutAppState::get_mut`].
+    #[track_caller]
+    pub fn get_mut(&self) -> HistoryMutAppStateLock<'_, T> {
+        HistoryMutAppStateLock::new(&self.0)
+    }
+
+    /// Reverts the last undo step.
+    /// Returns `false` if there is nothing to undo.
+    #[track_caller]
+    pub fn undo(&self) -> bool {
+        let mut lock = MutAppStateLock::new(&self.0.state);
+        let mut versions = self.0.versions();
+        match versions.undo.pop_back() {
+            Some(previous) => {
+                versions.redo.push(std::mem::replace(&mut *lock, previous));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reapplies the last undone step.
+    /// Returns `false` if there is nothing to redo.
+    #[track_caller]
+    pub fn redo(&self) -> bool {
+        let mut lock = MutAppStateLock::new(&self.0.state);
+        let mut versions = self.0.versions();
+        match versions.redo.pop() {
+            Some(next) => {
+                let current = std::mem::replace(&mut *lock, next);
+                versions.push_undo(current);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Starts grouping all changes into a single undo step
+    /// until the returned guard is dropped.
+    /// Groups may be nested, the changes are recorded once the outermost group ends.
+    ///
+    /// Groups apply to the state rather than the current thread, hence changes
+    /// made by other threads while a group is active are included in the group.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use app_state::{HistoryMutAppState, AppStateTrait};
+    ///
+    /// #[derive(Clone, Default)]
+    /// struct Document {
+    ///   lines: Vec<String>,
+    /// }
+    ///
+    /// fn main() {
+    ///   let doc = HistoryMutAppState::<Document>::get_or_insert_default();
+    ///   {
+    ///     let _group = doc.begin_group();
+    ///     doc.get_mut().lines.push("first".to_string());
+    ///     doc.get_mut().lines.push("second".to_string());
+    ///   }
+    ///
+    ///   assert_eq!(doc.history_len(), 1);
+    ///   doc.undo();
+    ///   assert!(doc.get_mut().lines.is_empty());
+    /// }
+    /// ```
+    pub fn begin_group(&self) -> HistoryGroup<'_, T> {
+        self.0.versions().group_depth += 1;
+        HistoryGroup { history: &self.0 }
+    }
+
+    /// Returns the number of steps which can be undone.
+    pub fn history_len(&self) -> usize {
+        self.0.versions().undo.len()
+    }
+
+    /// Returns the number of steps which can be redone.
+    pub fn redo_len(&self) -> usize {
+        self.0.versions().redo.len()
+    }
+
+    /// Sets the maximum number of undo steps kept by this state.
+    /// If more steps have been recorded, the oldest ones are discarded.
+    pub fn set_history_limit(&self, limit: usize) {
+        let mut versions = self.0.versions();
+        versions.limit = limit;
+        while versions.undo.len() > limit {
+            versions.undo.pop_front();
+        }
+    }
+
+    /// Discards all recorded versions.
+    pub fn clear_history(&self) {
+        let mut versions = self.0.versions();
+        versions.undo.clear();
+        versions.redo.clear();
+    }
+}
+
+impl<T: 'static + Send> CreateAppState<T> for HistoryMutAppState<T> {
+    fn new(state: T) -> HistoryMutAppState<T> {
+        let state = MutAppState::new(state);
+        state.set_metrics_key(StatsKey::of::<HistoryMutAppState<T>, T>(None));
+
+        HistoryMutAppState(Arc::new(History {
+            state,
+            versions: Mutex::new(Versions {
+                undo: VecDeque::new(),
+                redo: Vec::new(),
+                limit: DEFAULT_HISTORY_LIMIT,
+                group_depth: 0,
+                group_start: None,
+            }),
+        }))
+    }
+
+    fn set_metrics_name(&self, name: &str) {
+        self.0
+            .state
+            .set_metrics_key(StatsKey::of::<HistoryMutAppState<T>, T>(Some(name)));
+    }
+}
+
+impl<T: 'static + Send> AppStateTrait<T, HistoryMutAppState<T>> for HistoryMutAppState<T> {}
+
+impl<T: 'static + Send> Clone for HistoryMutAppState<T> {
+    fn clone(&self) -> HistoryMutAppState<T> {
+        HistoryMutAppState(self.0.clone())
+    }
+}
+
+/// A guard grouping all changes of a [`HistoryMutAppState`] into a single undo step.
+/// Created by [`HistoryMutAppState::begin_group`].
+pub struct HistoryGroup<'a, T: 'static + Send> {
+    history: &'a History<T>,
+}
+
+impl<T: 'static + Send> Drop for HistoryGroup<'_, T> {
+    fn drop(&mut self) {
+        let mut versions = self.history.versions();
+        versions.group_depth -= 1;
+        if versions.group_depth == 0 {
+            if let Some(start) = versions.group_start.take() {
+                versions.push_undo(start);
+            }
+        }
+    }
+}
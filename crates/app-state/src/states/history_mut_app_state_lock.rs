@@ -0,0 +1,57 @@
+use crate::states::history_mut_app_state::History;
+use crate::MutAppStateLock;
+use std::ops::{Deref, DerefMut};
+use std::thread;
+
+/// The lock guard for a history-tracking mutable app state.
+/// The version before the first mutable dereference is kept
+/// and recorded as an undo step when this guard is dropped.
+/// If the guard is dropped while panicking, the changes may be incomplete,
+/// hence no undo step is recorded.
+pub struct HistoryMutAppStateLock<'a, T: 'static + Send> {
+    lock: MutAppStateLock<'a, T>,
+    history: &'a History<T>,
+    before: Option<T>,
+}
+
+impl<'a, T: 'static + Clone + Send> HistoryMutAppStateLock<'a, T> {
+    #[track_caller]
+    pub(crate) fn new(history: &'a History<T>) -> HistoryMutAppStateLock<'a, T> {
+        HistoryMutAppStateLock {
+            lock: MutAppStateLock::new(history.state()),
+            history,
+            before: None,
+        }
+    }
+}
+
+impl<'a, T: 'static + Send> Deref for HistoryMutAppStateLock<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.lock
+    }
+}
+
+impl<'a, T: 'static + Clone + Send> DerefMut for HistoryMutAppStateLock<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        if self.before.is_none() {
+            self.before = Some(T::clone(&self.lock));
+        }
+
+        &mut self.lock
+    }
+}
+
+impl<'a, T: 'static + Send> Drop for HistoryMutAppStateLock<'a, T> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            return;
+        }
+
+        // Recorded while holding the lock, so that steps are recorded in order
+        if let Some(before) = self.before.take() {
+            self.history.commit(before);
+        }
+    }
+}
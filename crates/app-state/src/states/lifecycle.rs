@@ -0,0 +1,154 @@
+use crate::states::store::with_current_store;
+use crate::{AppState, CreateAppState, MutAppState, MutAppStateLock, RwAppState, SwapAppState};
+use std::marker::PhantomData;
+
+/// Hooks which are called when a state is initialized and when it is shut down.
+///
+/// Hooks are only called for states initialized using
+/// [`AppStateTrait::init_with_lifecycle`] or the `init_default_state`
+/// macros. [`shutdown`] calls the `on_shutdown` hooks in reverse order
+/// of initialization and drops all states afterwards.
+///
+/// Implementing this trait for a type `T` implements it for all states holding
+/// a `T` as well, e.g. `AppState<T>` or `MutAppState<T>`. The hooks of mutable
+/// states are called while the state is locked and skipped if the state
+/// has been poisoned and the poison can not be recovered.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{AppState, AppStateTrait, StateLifecycle, shutdown};
+///
+/// struct Log {
+///   file: String,
+/// }
+///
+/// impl StateLifecycle for Log {
+///   fn on_init(&self) {
+///     println!("Opened {}", self.file);
+///   }
+///
+///   fn on_shutdown(&self) {
+///     println!("Flushed {}", self.file);
+///   }
+/// }
+///
+/// fn main() {
+///   AppState::init_with_lifecycle(Log { file: "app.log".to_string() });
+///   shutdown();
+///   assert!(AppState::<Log>::try_get().is_err());
+/// }
+/// ```
+pub trait StateLifecycle {
+    /// Called after the state has been initialized.
+    fn on_init(&self) {}
+
+    /// Called by [`shutdown`] before the state is dropped.
+    fn on_shutdown(&self) {}
+}
+
+impl<T: ?Sized + StateLifecycle> StateLifecycle for AppState<T> {
+    fn on_init(&self) {
+        self.get_ref().on_init();
+    }
+
+    fn on_shutdown(&self) {
+        self.get_ref().on_shutdown();
+    }
+}
+
+impl<T: 'static + Send + StateLifecycle> StateLifecycle for MutAppState<T> {
+    fn on_init(&self) {
+        match MutAppStateLock::lock_checked(self) {
+            Ok(state) => state.on_init(),
+            Err(_err) => {
+                #[cfg(feature = "log")]
+                log::error!("Skipping the init hook: {}", _err);
+            }
+        }
+    }
+
+    fn on_shutdown(&self) {
+        // A poisoned state must not abort the shutdown of the remaining states
+        match MutAppStateLock::lock_checked(self) {
+            Ok(state) => state.on_shutdown(),
+            Err(_err) => {
+                #[cfg(feature = "log")]
+                log::error!("Skipping the shutdown hook: {}", _err);
+            }
+        }
+    }
+}
+
+impl<T: 'static + Send + Sync + StateLifecycle> StateLifecycle for RwAppState<T> {
+    fn on_init(&self) {
+        self.read().on_init();
+    }
+
+    fn on_shutdown(&self) {
+        self.read().on_shutdown();
+    }
+}
+
+impl<T: 'static + Send + Sync + StateLifecycle> StateLifecycle for SwapAppState<T> {
+    fn on_init(&self) {
+        self.load().on_init();
+    }
+
+    fn on_shutdown(&self) {
+        self.load().on_shutdown();
+    }
+}
+
+/// Calls the shutdown hooks of all states in reverse order of their
+/// initialization and removes all states from the state store afterwards.
+/// The hooks may still access other states.
+///
+/// If the current thread has been isolated using
+/// [`StateStore::isolated`](crate::StateStore::isolated),
+/// this only shuts down the states of the isolated store.
+pub fn shutdown() {
+    with_current_store(|store| store.shutdown());
+}
+
+/// Initializes a state with the default value of `T`, registering its
+/// lifecycle hooks if the state implements [`StateLifecycle`].
+/// Used by the `init_default_state` macros.
+#[doc(hidden)]
+pub struct InitWith<U, T>(PhantomData<(U, T)>);
+
+impl<U, T> InitWith<U, T> {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        InitWith(PhantomData)
+    }
+}
+
+#[doc(hidden)]
+pub trait InitLifecycle {
+    fn init_state(&self);
+}
+
+impl<T, U> InitLifecycle for InitWith<U, T>
+where
+    T: 'static + Send + Default,
+    U: 'static + CreateAppState<T> + StateLifecycle + Clone + Send,
+{
+    fn init_state(&self) {
+        with_current_store(|store| store.insert_with_lifecycle(U::new_default()));
+    }
+}
+
+#[doc(hidden)]
+pub trait InitPlain {
+    fn init_state(&self);
+}
+
+impl<T, U> InitPlain for &InitWith<U, T>
+where
+    T: 'static + Send + Default,
+    U: 'static + CreateAppState<T> + Clone + Send,
+{
+    fn init_state(&self) {
+        with_current_store(|store| store.insert_state(None, U::new_default()));
+    }
+}
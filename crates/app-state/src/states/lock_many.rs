@@ -0,0 +1,125 @@
+use crate::states::deadlock::LockSite;
+use crate::{MutAppState, MutAppStateLock};
+
+/// A tuple of mutable states which can be locked at once.
+/// Implemented for tuples of up to twelve `&MutAppState<T>`.
+///
+/// The states are always locked in the same global order,
+/// regardless of their order in the tuple. Thus, two threads
+/// locking the same states at once can never deadlock each other.
+/// Use [`MutAppState::lock_many`] or [`lock_all!`](crate::lock_all) to lock the states.
+pub trait LockMany<'a> {
+    /// The locks of the states, in the order of the states.
+    type Locks;
+
+    /// Locks all states, recording the function they are injected into.
+    /// Used by `MutAppState::lock_many` and the `stateful` macro.
+    #[doc(hidden)]
+    fn lock_in(self, function: Option<&'static str>) -> Self::Locks;
+}
+
+impl MutAppState<()> {
+    /// Locks several mutable states at once.
+    /// The states are locked in a canonical order, which is the same in all threads,
+    /// and the locks are returned in the order of the states.
+    /// Locking the same state twice panics with the `deadlock-detection` feature
+    /// enabled and deadlocks otherwise.
+    /// Poisoned states are handled like in [`MutAppState::get_mut`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// use app_state::{MutAppState, AppStateTrait};
+    ///
+    /// #[derive(Default)]
+    /// struct Accounts {
+    ///   balance: u32,
+    /// }
+    ///
+    /// #[derive(Default)]
+    /// struct Audit {
+    ///   entries: Vec<u32>,
+    /// }
+    ///
+    /// fn main() {
+    ///   let accounts = MutAppState::<Accounts>::get_or_insert_default();
+    ///   let audit = MutAppState::<Audit>::get_or_insert_default();
+    ///
+    ///   let (mut accounts, mut audit) = MutAppState::lock_many((&accounts, &audit));
+    ///   accounts.balance += 10;
+    ///   audit.entries.push(10);
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn lock_many<'a, L: LockMany<'a>>(states: L) -> L::Locks {
+        states.lock_in(None)
+    }
+}
+
+/// Locks several mutable states at once using [`MutAppState::lock_many`].
+///
+/// # Examples
+/// ```rust
+/// use app_state::{lock_all, MutAppState, AppStateTrait};
+///
+/// #[derive(Default)]
+/// struct First(u32);
+///
+/// #[derive(Default)]
+/// struct Second(u32);
+///
+/// fn main() {
+///   let first = MutAppState::<First>::get_or_insert_default();
+///   let second = MutAppState::<Second>::get_or_insert_default();
+///
+///   let (mut first, mut second) = lock_all!(first, second);
+///   std::mem::swap(&mut first.0, &mut second.0);
+/// }
+/// ```
+#[macro_export]
+macro_rules! lock_all {
+    ($($state:expr),+ $(,)?) => {
+        $crate::MutAppState::lock_many(($(&$state,)+))
+    };
+}
+
+macro_rules! impl_lock_many {
+    ($($index:tt: $T:ident),+) => {
+        impl<'a, $($T: 'static + Send),+> LockMany<'a> for ($(&'a MutAppState<$T>,)+) {
+            type Locks = ($(MutAppStateLock<'a, $T>,)+);
+
+            #[track_caller]
+            fn lock_in(self, function: Option<&'static str>) -> Self::Locks {
+                let site = match function {
+                    Some(function) => LockSite::Function(function),
+                    None => LockSite::caller(),
+                };
+
+                let mut order = [$(($index, self.$index.id())),+];
+                order.sort_by_key(|(_, id)| *id);
+
+                let mut locks = ($(None::<MutAppStateLock<'a, $T>>,)+);
+                for (index, _) in order {
+                    match index {
+                        $($index => locks.$index = Some(MutAppStateLock::lock_or_panic(self.$index, site)),)+
+                        _ => unreachable!(),
+                    }
+                }
+
+                ($(locks.$index.expect("every state is locked"),)+)
+            }
+        }
+    };
+}
+
+impl_lock_many!(0: A);
+impl_lock_many!(0: A, 1: B);
+impl_lock_many!(0: A, 1: B, 2: C);
+impl_lock_many!(0: A, 1: B, 2: C, 3: D);
+impl_lock_many!(0: A, 1: B, 2: C, 3: D, 4: E);
+impl_lock_many!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F);
+impl_lock_many!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G);
+impl_lock_many!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H);
+impl_lock_many!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I);
+impl_lock_many!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I, 9: J);
+impl_lock_many!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I, 9: J, 10: K);
+impl_lock_many!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I, 9: J, 10: K, 11: L);
@@ -0,0 +1,407 @@
+//! Usage metrics of states, recorded if the `metrics` feature is enabled.
+//! In all other builds, the recording functions in this module do nothing.
+
+#[cfg(feature = "metrics")]
+mod recording {
+    use std::collections::BTreeMap;
+    use std::fmt::Write;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex, PoisonError, RwLock};
+    use std::time::{Duration, Instant};
+
+    /// Identifies the metrics of a single state,
+    /// which is the state of kind `kind` holding a `type_name` with the name `name`.
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct StatsKey {
+        type_name: &'static str,
+        kind: &'static str,
+        name: Option<String>,
+        /// The type name of the state itself, used to look up states by their type.
+        state: &'static str,
+    }
+
+    impl StatsKey {
+        pub(crate) fn of<U: ?Sized, T: ?Sized>(name: Option<&str>) -> StatsKey {
+            let state = std::any::type_name::<U>();
+            let path = state.split('<').next().unwrap_or(state);
+
+            StatsKey {
+                type_name: std::any::type_name::<T>(),
+                kind: path.rsplit("::").next().unwrap_or(path),
+                name: name.map(str::to_string),
+                state,
+            }
+        }
+
+        /// Returns the type name of the value of the state.
+        pub fn type_name(&self) -> &'static str {
+            self.type_name
+        }
+
+        /// Returns the kind of the state, for example `MutAppState`.
+        pub fn kind(&self) -> &'static str {
+            self.kind
+        }
+
+        /// Returns the name of the state, if it is a named state.
+        pub fn name(&self) -> Option<&str> {
+            self.name.as_deref()
+        }
+    }
+
+    #[derive(Default)]
+    struct Counters {
+        gets: AtomicU64,
+        lock_acquisitions: AtomicU64,
+        total_wait_nanos: AtomicU64,
+        max_wait_nanos: AtomicU64,
+        total_hold_nanos: AtomicU64,
+        max_hold_nanos: AtomicU64,
+    }
+
+    impl Counters {
+        fn stats(&self) -> StateStats {
+            let duration = |nanos: &AtomicU64| Duration::from_nanos(nanos.load(Ordering::Relaxed));
+
+            StateStats {
+                gets: self.gets.load(Ordering::Relaxed),
+                lock_acquisitions: self.lock_acquisitions.load(Ordering::Relaxed),
+                total_wait_time: duration(&self.total_wait_nanos),
+                max_wait_time: duration(&self.max_wait_nanos),
+                total_hold_time: duration(&self.total_hold_nanos),
+                max_hold_time: duration(&self.max_hold_nanos),
+            }
+        }
+    }
+
+    static COUNTERS: RwLock<BTreeMap<StatsKey, Arc<Counters>>> = RwLock::new(BTreeMap::new());
+
+    fn counters(key: &StatsKey) -> Arc<Counters> {
+        if let Some(counters) = COUNTERS
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(key)
+        {
+            return counters.clone();
+        }
+
+        COUNTERS
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(key.clone())
+            .or_default()
+            .clone()
+    }
+
+    fn record_duration(total: &AtomicU64, max: &AtomicU64, duration: Duration) {
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        total.fetch_add(nanos, Ordering::Relaxed);
+        max.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_get(key: StatsKey) {
+        counters(&key).gets.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The key the lock metrics of a `MutAppState` are recorded for,
+    /// if it differs from the key of an unnamed `MutAppState`.
+    pub(crate) struct LockKey(Mutex<Option<StatsKey>>);
+
+    impl LockKey {
+        pub(crate) fn new() -> LockKey {
+            LockKey(Mutex::new(None))
+        }
+
+        pub(crate) fn set(&self, key: StatsKey) {
+            *self.0.lock().unwrap_or_else(PoisonError::into_inner) = Some(key);
+        }
+    }
+
+    pub(crate) struct LockTiming {
+        counters: Arc<Counters>,
+        started: Instant,
+    }
+
+    impl LockTiming {
+        pub(crate) fn start(key: &LockKey, default: fn() -> StatsKey) -> LockTiming {
+            let counters = match &*key.0.lock().unwrap_or_else(PoisonError::into_inner) {
+                Some(key) => counters(key),
+                None => counters(&default()),
+            };
+
+            LockTiming {
+                counters,
+                started: Instant::now(),
+            }
+        }
+
+        pub(crate) fn acquired(&self) -> LockHold {
+            let counters = &self.counters;
+            counters.lock_acquisitions.fetch_add(1, Ordering::Relaxed);
+            record_duration(
+                &counters.total_wait_nanos,
+                &counters.max_wait_nanos,
+                self.started.elapsed(),
+            );
+
+            LockHold {
+                counters: self.counters.clone(),
+                acquired: Instant::now(),
+            }
+        }
+    }
+
+    /// A lock which records its hold time once it is dropped.
+    pub(crate) struct LockHold {
+        counters: Arc<Counters>,
+        acquired: Instant,
+    }
+
+    impl Drop for LockHold {
+        fn drop(&mut self) {
+            record_duration(
+                &self.counters.total_hold_nanos,
+                &self.counters.max_hold_nanos,
+                self.acquired.elapsed(),
+            );
+        }
+    }
+
+    /// The usage metrics of a single state.
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    #[non_exhaustive]
+    pub struct StateStats {
+        /// The number of times the state has been retrieved using `get` or one of its variants.
+        pub gets: u64,
+        /// The number of times a `MutAppStateLock` of the state has been acquired.
+        pub lock_acquisitions: u64,
+        /// The total time spent waiting for the state to be unlocked.
+        pub total_wait_time: Duration,
+        /// The longest time spent waiting for the state to be unlocked.
+        pub max_wait_time: Duration,
+        /// The total time the state has been locked.
+        pub total_hold_time: Duration,
+        /// The longest time the state has been locked at once.
+        pub max_hold_time: Duration,
+    }
+
+    /// A snapshot of the usage metrics of all states, keyed by [`StatsKey`].
+    /// Different kinds of states holding the same type and named states
+    /// have their own metrics. The metrics are recorded for the whole process,
+    /// including all [`StateStore`](crate::StateStore)s.
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct Stats {
+        states: BTreeMap<StatsKey, StateStats>,
+    }
+
+    impl Stats {
+        /// Returns the metrics of the state `U`, for example `MutAppState<T>`.
+        pub fn get<U: ?Sized>(&self) -> Option<&StateStats> {
+            self.find(std::any::type_name::<U>(), None)
+        }
+
+        /// Returns the metrics of the state `U` with the given name.
+        pub fn get_named<U: ?Sized>(&self, name: &str) -> Option<&StateStats> {
+            self.find(std::any::type_name::<U>(), Some(name))
+        }
+
+        fn find(&self, state: &str, name: Option<&str>) -> Option<&StateStats> {
+            self.states
+                .iter()
+                .find(|(key, _)| key.state == state && key.name() == name)
+                .map(|(_, stats)| stats)
+        }
+
+        /// Returns the metrics of all states, ordered by the type name of their value.
+        pub fn iter(&self) -> impl Iterator<Item = (&StatsKey, &StateStats)> {
+            self.states.iter()
+        }
+
+        /// Formats the metrics in the Prometheus text exposition format.
+        /// Every metric is labeled with the type name of the value of the state
+        /// and the kind of the state. Named states are labeled with their name as well.
+        ///
+        /// # Examples
+        /// ```rust
+        /// use app_state::{stats, AppState, AppStateTrait};
+        ///
+        /// struct Config {
+        ///   url: String,
+        /// }
+        ///
+        /// fn main() {
+        ///   AppState::init(Config { url: "localhost".to_string() });
+        ///   AppState::<Config>::get();
+        ///
+        ///   let metrics = stats().to_prometheus();
+        ///   let type_name = std::any::type_name::<Config>();
+        ///   assert!(metrics.contains(&format!(
+        ///     "app_state_gets_total{{state=\"{type_name}\",kind=\"AppState\"}} 1"
+        ///   )));
+        /// }
+        /// ```
+        pub fn to_prometheus(&self) -> String {
+            type Metric = (
+                &'static str,
+                &'static str,
+                &'static str,
+                fn(&StateStats) -> String,
+            );
+            let metrics: [Metric; 6] = [
+                (
+                    "app_state_gets_total",
+                    "counter",
+                    "Number of times the state has been retrieved.",
+                    |s| s.gets.to_string(),
+                ),
+                (
+                    "app_state_lock_acquisitions_total",
+                    "counter",
+                    "Number of times the state has been locked.",
+                    |s| s.lock_acquisitions.to_string(),
+                ),
+                (
+                    "app_state_lock_wait_seconds_total",
+                    "counter",
+                    "Total time spent waiting for the state to be unlocked.",
+                    |s| seconds(s.total_wait_time),
+                ),
+                (
+                    "app_state_lock_wait_seconds_max",
+                    "gauge",
+                    "Longest time spent waiting for the state to be unlocked.",
+                    |s| seconds(s.max_wait_time),
+                ),
+                (
+                    "app_state_lock_hold_seconds_total",
+                    "counter",
+                    "Total time the state has been locked.",
+                    |s| seconds(s.total_hold_time),
+                ),
+                (
+                    "app_state_lock_hold_seconds_max",
+                    "gauge",
+                    "Longest time the state has been locked at once.",
+                    |s| seconds(s.max_hold_time),
+                ),
+            ];
+
+            let mut out = String::new();
+            for (name, kind, help, value) in metrics {
+                let _ = writeln!(out, "# HELP {name} {help}");
+                let _ = writeln!(out, "# TYPE {name} {kind}");
+                for (key, stats) in self.iter() {
+                    let _ = write!(
+                        out,
+                        "{name}{{state=\"{}\",kind=\"{}\"",
+                        escape_label(key.type_name()),
+                        key.kind()
+                    );
+                    if let Some(state_name) = key.name() {
+                        let _ = write!(out, ",name=\"{}\"", escape_label(state_name));
+                    }
+                    let _ = writeln!(out, "}} {}", value(stats));
+                }
+            }
+
+            out
+        }
+    }
+
+    fn seconds(duration: Duration) -> String {
+        duration.as_secs_f64().to_string()
+    }
+
+    fn escape_label(value: &str) -> String {
+        value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    }
+
+    /// Returns a snapshot of the usage metrics of all states.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use app_state::{stats, MutAppState, AppStateTrait};
+    ///
+    /// #[derive(Default)]
+    /// struct Counter(u32);
+    ///
+    /// fn main() {
+    ///   let counter = MutAppState::<Counter>::get_or_insert_default();
+    ///   counter.get_mut().0 += 1;
+    ///
+    ///   let stats = stats();
+    ///   let counter = stats.get::<MutAppState<Counter>>().unwrap();
+    ///   assert_eq!(counter.gets, 1);
+    ///   assert_eq!(counter.lock_acquisitions, 1);
+    /// }
+    /// ```
+    pub fn stats() -> Stats {
+        let counters = COUNTERS.read().unwrap_or_else(PoisonError::into_inner);
+        Stats {
+            states: counters
+                .iter()
+                .map(|(key, counters)| (key.clone(), counters.stats()))
+                .collect(),
+        }
+    }
+
+    /// Resets the usage metrics of all states.
+    pub fn reset_stats() {
+        COUNTERS
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+mod recording {
+    pub(crate) struct StatsKey;
+
+    impl StatsKey {
+        #[allow(clippy::extra_unused_type_parameters)]
+        pub(crate) fn of<U: ?Sized, T: ?Sized>(_: Option<&str>) -> StatsKey {
+            StatsKey
+        }
+    }
+
+    pub(crate) fn record_get(_: StatsKey) {}
+
+    pub(crate) struct LockKey;
+
+    impl LockKey {
+        pub(crate) fn new() -> LockKey {
+            LockKey
+        }
+
+        pub(crate) fn set(&self, _: StatsKey) {}
+    }
+
+    pub(crate) struct LockTiming;
+
+    impl LockTiming {
+        pub(crate) fn start(_: &LockKey, _: fn() -> StatsKey) -> LockTiming {
+            LockTiming
+        }
+
+        pub(crate) fn acquired(&self) -> LockHold {
+            LockHold
+        }
+    }
+
+    pub(crate) struct LockHold;
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) use recording::StatsKey;
+#[cfg(feature = "metrics")]
+pub use recording::{reset_stats, stats, StateStats, Stats, StatsKey};
+pub(crate) use recording::{LockHold, LockKey, LockTiming};
+
+/// Records a call to `get` or one of its variants for the state `U` holding a `T`.
+pub(crate) fn record_get<U: ?Sized, T: ?Sized>(name: Option<&str>) {
+    recording::record_get(StatsKey::of::<U, T>(name));
+}
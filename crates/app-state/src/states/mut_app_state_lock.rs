@@ -1,11 +1,17 @@
-use crate::MutAppState;
+use crate::states::deadlock::{self, HeldLock, LockSite};
+use crate::states::metrics::{LockHold, LockTiming};
+use crate::{MutAppState, StateError};
 use std::ops::{Deref, DerefMut};
-use std::sync::MutexGuard;
+use std::sync::{MutexGuard, PoisonError, TryLockError};
+use std::thread;
+use std::time::{Duration, Instant};
 
 /// The lock guard for a mutable app state.
 /// This is a wrapper around `MutexGuard`.
 /// When this guard is dropped, the lock will be released.
 /// As this locks the state, no other thread can access the state until this guard is dropped.
+/// If the state has been mutably dereferenced through this guard, the subscribers
+/// of the state are notified before the lock is released.
 ///
 /// # Examples
 /// ```rust
@@ -20,23 +26,190 @@ use std::sync::MutexGuard;
 ///   state.counter += 1;
 /// }
 /// ```
-pub struct MutAppStateLock<'a, T: ?Sized>(MutexGuard<'a, T>);
+pub struct MutAppStateLock<'a, T: ?Sized> {
+    /// Only `None` after the guard has been taken by `into_inner`.
+    guard: Option<MutexGuard<'a, T>>,
+    state: &'a MutAppState<T>,
+    modified: bool,
+    _held: HeldLock,
+    _hold: LockHold,
+}
 
 impl<'a, T: 'static + Send> MutAppStateLock<'a, T> {
+    /// Locks the given state.
+    /// If the state has been poisoned, this behaves according to the
+    /// [`PoisonPolicy`](crate::PoisonPolicy) of the state and panics
+    /// if the poison can not be recovered.
+    ///
+    /// With the `deadlock-detection` feature enabled, this panics if the state
+    /// is already locked by the current thread or if the current thread holds
+    /// a state which has been locked while holding this state before.
+    #[track_caller]
     pub fn new(inner: &'a MutAppState<T>) -> MutAppStateLock<'a, T> {
-        MutAppStateLock(inner.lock().unwrap())
+        Self::lock_or_panic(inner, LockSite::caller())
+    }
+
+    /// Locks the given state, recording the function it is injected into.
+    /// Used by the `stateful` macro.
+    #[doc(hidden)]
+    pub fn new_in(inner: &'a MutAppState<T>, function: &'static str) -> MutAppStateLock<'a, T> {
+        Self::lock_or_panic(inner, LockSite::Function(function))
+    }
+
+    /// Locks the given state, blocking until it is unlocked.
+    /// If the state has been poisoned, this behaves according to the
+    /// [`PoisonPolicy`](crate::PoisonPolicy) of the state and returns `Err`
+    /// if the poison can not be recovered.
+    /// Deadlocks are detected like in [`MutAppStateLock::new`].
+    #[track_caller]
+    pub fn lock_checked(inner: &'a MutAppState<T>) -> Result<MutAppStateLock<'a, T>, StateError> {
+        Self::lock(inner, LockSite::caller())
+    }
+
+    /// Locks the given state without blocking.
+    /// If the state is currently locked, this will return [`StateError::WouldBlock`].
+    /// Poisoned states are handled like in [`MutAppStateLock::lock_checked`].
+    #[track_caller]
+    pub fn try_lock(inner: &'a MutAppState<T>) -> Result<MutAppStateLock<'a, T>, StateError> {
+        let timing = inner.lock_timing();
+        Self::try_lock_at(inner, LockSite::caller(), &timing)
+    }
+
+    /// Locks the given state, waiting at most `timeout` for the state to be unlocked.
+    /// If the state could not be locked in time, this will return [`StateError::LockTimeout`].
+    /// Poisoned states are handled like in [`MutAppStateLock::lock_checked`].
+    /// The state is polled like in [`MutAppState::get_mut_timeout`].
+    #[track_caller]
+    pub fn with_timeout(
+        inner: &'a MutAppState<T>,
+        timeout: Duration,
+    ) -> Result<MutAppStateLock<'a, T>, StateError> {
+        Self::lock_with_timeout(inner, timeout, LockSite::caller())
+    }
+
+    /// Locks the given state with a timeout, recording the function it is injected into.
+    /// Used by the `stateful` macro.
+    #[doc(hidden)]
+    pub fn with_timeout_in(
+        inner: &'a MutAppState<T>,
+        timeout: Duration,
+        function: &'static str,
+    ) -> Result<MutAppStateLock<'a, T>, StateError> {
+        Self::lock_with_timeout(inner, timeout, LockSite::Function(function))
+    }
+
+    pub(crate) fn lock_or_panic(
+        inner: &'a MutAppState<T>,
+        site: LockSite,
+    ) -> MutAppStateLock<'a, T> {
+        match Self::lock(inner, site) {
+            Ok(lock) => lock,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    fn lock(
+        inner: &'a MutAppState<T>,
+        site: LockSite,
+    ) -> Result<MutAppStateLock<'a, T>, StateError> {
+        let timing = inner.lock_timing();
+        let waiting = deadlock::wait_for::<T>(inner.id(), site, true);
+        let guard = inner.lock().map_err(PoisonError::into_inner);
+        let held = waiting.acquired();
+
+        Self::finish(inner, guard, held, &timing)
+    }
+
+    fn try_lock_at(
+        inner: &'a MutAppState<T>,
+        site: LockSite,
+        timing: &LockTiming,
+    ) -> Result<MutAppStateLock<'a, T>, StateError> {
+        let guard = match inner.try_lock() {
+            Ok(guard) => Ok(guard),
+            Err(TryLockError::WouldBlock) => return Err(StateError::would_block::<T>()),
+            Err(TryLockError::Poisoned(err)) => Err(err.into_inner()),
+        };
+        let held = deadlock::wait_for::<T>(inner.id(), site, false).acquired();
+
+        Self::finish(inner, guard, held, timing)
+    }
+
+    fn lock_with_timeout(
+        inner: &'a MutAppState<T>,
+        timeout: Duration,
+        site: LockSite,
+    ) -> Result<MutAppStateLock<'a, T>, StateError> {
+        let timing = inner.lock_timing();
+        let deadline = Instant::now() + timeout;
+        let mut backoff = Duration::from_micros(10);
+
+        loop {
+            match Self::try_lock_at(inner, site, &timing) {
+                Err(StateError::WouldBlock { .. }) => {}
+                res => return res,
+            }
+
+            // The standard mutex can not be locked with a timeout,
+            // hence poll it with an increasing interval
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(StateError::lock_timeout::<T>());
+            }
+
+            thread::sleep(backoff.min(deadline - now));
+            backoff = (backoff * 2).min(Duration::from_millis(1));
+        }
+    }
+
+    /// Wraps the acquired `guard`, recovering it if the state has been poisoned.
+    fn finish(
+        inner: &'a MutAppState<T>,
+        guard: Result<MutexGuard<'a, T>, MutexGuard<'a, T>>,
+        held: HeldLock,
+        timing: &LockTiming,
+    ) -> Result<MutAppStateLock<'a, T>, StateError> {
+        let guard = match guard {
+            Ok(guard) => guard,
+            Err(guard) => inner.recover(guard)?,
+        };
+
+        Ok(MutAppStateLock {
+            guard: Some(guard),
+            state: inner,
+            modified: false,
+            _held: held,
+            _hold: timing.acquired(),
+        })
     }
 }
 
 impl<'a, T: ?Sized> MutAppStateLock<'a, T> {
+    /// Returns the version of the locked state,
+    /// excluding the changes made through this lock.
+    /// See [`MutAppState::version`] for details.
+    pub fn version(&self) -> u64 {
+        self.state.version()
+    }
+
     /// Returns reference to inner `T`.
     pub fn get_ref(&self) -> &MutexGuard<'a, T> {
-        &self.0
+        self.guard.as_ref().unwrap()
     }
 
-    /// Unwraps to the internal `Arc<T>`
-    pub fn into_inner(self) -> MutexGuard<'a, T> {
-        self.0
+    /// Unwraps to the internal `MutexGuard`.
+    /// The returned guard is no longer considered by the deadlock detection,
+    /// its hold time is not included in the metrics of the state and
+    /// changes made through it are not passed to the subscribers of the state.
+    /// Changes made through this lock before are passed to the subscribers.
+    pub fn into_inner(mut self) -> MutexGuard<'a, T> {
+        if self.modified {
+            self.modified = false;
+            self.state.increment_version();
+            self.state.subscribers().notify(&**self.get_ref());
+        }
+
+        self.guard.take().unwrap()
     }
 }
 
@@ -44,12 +217,26 @@ impl<'a, T: ?Sized> Deref for MutAppStateLock<'a, T> {
     type Target = T;
 
     fn deref(&self) -> &T {
-        &self.0
+        self.get_ref()
     }
 }
 
 impl<'a, T: ?Sized> DerefMut for MutAppStateLock<'a, T> {
     fn deref_mut(&mut self) -> &mut T {
-        &mut self.0
+        self.modified = true;
+        self.guard.as_mut().unwrap()
+    }
+}
+
+impl<'a, T: ?Sized> Drop for MutAppStateLock<'a, T> {
+    fn drop(&mut self) {
+        if let (true, Some(guard)) = (self.modified, &self.guard) {
+            self.state.increment_version();
+
+            // Changes made before a panic may be incomplete, hence they are not published
+            if !thread::panicking() {
+                self.state.subscribers().notify(guard);
+            }
+        }
     }
 }
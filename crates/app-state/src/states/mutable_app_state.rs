@@ -1,7 +1,34 @@
+use crate::states::deadlock;
+use crate::states::metrics::{LockKey, LockTiming, StatsKey};
+use crate::states::poison::PoisonHandling;
+use crate::states::store::with_current_store;
+use crate::states::subscription::Subscribers;
 use crate::states::traits::CreateAppState;
-use crate::{AppStateTrait, MutAppStateLock};
+use crate::{AppStateTrait, MutAppStateLock, PoisonPolicy, StateError, Subscription};
+use std::fmt::{Debug, Formatter};
 use std::ops::Deref;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// The data of a mutable state which is shared by all of its handles.
+/// Handles created from a mutex using `MutAppState::from` get their own data.
+struct Inner<T: ?Sized> {
+    poison: PoisonHandling<T>,
+    subscribers: Arc<Subscribers<T>>,
+    /// Incremented whenever a lock which mutated the state is released.
+    version: AtomicU64,
+    /// The key the metrics of locks of this state are recorded for.
+    lock_key: LockKey,
+    /// Kept in its own `Arc` as it is exposed through `Deref` and `into_inner`.
+    mutex: Arc<Mutex<T>>,
+}
+
+impl<T: ?Sized> Drop for Inner<T> {
+    fn drop(&mut self) {
+        deadlock::forget(self as *const Inner<T> as *const () as usize);
+    }
+}
 
 /// A mutable app state.
 ///
@@ -19,28 +46,397 @@ use std::sync::{Arc, Mutex};
 ///   state.counter += 1;
 /// }
 /// ```
-#[derive(Debug)]
-pub struct MutAppState<T: ?Sized>(Arc<Mutex<T>>);
+pub struct MutAppState<T: ?Sized> {
+    inner: Arc<Inner<T>>,
+}
 
 impl<T: 'static + Send> MutAppState<T> {
     /// Returns reference to inner `T`.
-    pub fn get_mut(&self) -> MutAppStateLock<T> {
-        MutAppStateLock::new(&self)
+    /// If the state has been poisoned, this behaves according
+    /// to the [`PoisonPolicy`] of the state and panics if the poison can not be recovered.
+    #[track_caller]
+    pub fn get_mut(&self) -> MutAppStateLock<'_, T> {
+        MutAppStateLock::new(self)
+    }
+
+    /// Returns reference to inner `T`, blocking until the state is unlocked.
+    /// If the state has been poisoned, this behaves according
+    /// to the [`PoisonPolicy`] of the state and returns
+    /// [`StateError::Poisoned`] if the poison can not be recovered.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use app_state::{MutAppState, AppStateTrait, StateError};
+    ///
+    /// #[derive(Default)]
+    /// struct MyState {
+    ///   counter: u32,
+    /// }
+    ///
+    /// fn main() {
+    ///   let state = MutAppState::<MyState>::get_or_insert_default();
+    ///   let cloned = state.clone();
+    ///   std::thread::spawn(move || {
+    ///     let _lock = cloned.get_mut();
+    ///     panic!("Poisoning the state");
+    ///   })
+    ///   .join()
+    ///   .unwrap_err();
+    ///
+    ///   assert!(matches!(state.get_mut_checked(), Err(StateError::Poisoned { .. })));
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn get_mut_checked(&self) -> Result<MutAppStateLock<'_, T>, StateError> {
+        MutAppStateLock::lock_checked(self)
+    }
+
+    /// Returns reference to inner `T` without blocking.
+    /// If the state is currently locked, this returns `None`.
+    /// If the state has been poisoned, this behaves according
+    /// to the [`PoisonPolicy`] of the state and panics if the poison can not be recovered.
+    /// Use [`MutAppStateLock::try_lock`] in order to handle poisoned states as errors.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use app_state::{MutAppState, AppStateTrait};
+    ///
+    /// #[derive(Default)]
+    /// struct MyState {
+    ///   counter: u32,
+    /// }
+    ///
+    /// fn main() {
+    ///   let state = MutAppState::<MyState>::get_or_insert_default();
+    ///   let lock = state.get_mut();
+    ///
+    ///   assert!(state.try_get_mut().is_none());
+    ///
+    ///   drop(lock);
+    ///   assert!(state.try_get_mut().is_some());
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn try_get_mut(&self) -> Option<MutAppStateLock<'_, T>> {
+        match MutAppStateLock::try_lock(self) {
+            Ok(lock) => Some(lock),
+            Err(StateError::WouldBlock { .. }) => None,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Returns reference to inner `T`, waiting at most `timeout` for the state to be unlocked.
+    /// If the state could not be locked in time, this returns [`StateError::LockTimeout`].
+    /// Poisoned states are handled like in [`MutAppState::get_mut_checked`].
+    ///
+    /// As the standard mutex can not be locked with a timeout, this polls the state
+    /// with an increasing interval of up to one millisecond. Hence waiting threads are
+    /// not queued fairly and may acquire the lock up to one millisecond after it has
+    /// been released.
+    #[track_caller]
+    pub fn get_mut_timeout(&self, timeout: Duration) -> Result<MutAppStateLock<'_, T>, StateError> {
+        MutAppStateLock::with_timeout(self, timeout)
+    }
+
+    /// Sets the poison policy of this state.
+    /// This overrides the default policy set by
+    /// [`set_default_poison_policy`](crate::set_default_poison_policy)
+    /// and applies to all handles of this state.
+    ///
+    /// # Panics
+    /// Panics if `policy` is [`PoisonPolicy::ResetToDefault`] and no default
+    /// value has been registered for this state.
+    /// Use [`MutAppState::reset_to_default_on_poison`] in this case.
+    #[track_caller]
+    pub fn set_poison_policy(&self, policy: PoisonPolicy) {
+        if policy == PoisonPolicy::ResetToDefault && !self.inner.poison.has_reset() {
+            panic!(
+                "Cannot reset state {} to its default value, as no default value has been registered",
+                std::any::type_name::<T>()
+            );
+        }
+
+        self.inner.poison.set_policy(policy);
+    }
+
+    /// Returns the poison policy used by this state.
+    pub fn poison_policy(&self) -> PoisonPolicy {
+        self.inner.poison.policy()
+    }
+
+    /// Resets this state to its default value if it is locked while being poisoned.
+    /// This sets the poison policy of this state to [`PoisonPolicy::ResetToDefault`]
+    /// and registers the default value for states which have not been created from it.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use app_state::{MutAppState, AppStateTrait};
+    ///
+    /// #[derive(Default)]
+    /// struct MyState {
+    ///   counter: u32,
+    /// }
+    ///
+    /// fn main() {
+    ///   let state = MutAppState::<MyState>::get_or_insert_default();
+    ///   state.reset_to_default_on_poison();
+    ///
+    ///   let cloned = state.clone();
+    ///   std::thread::spawn(move || {
+    ///     let mut lock = cloned.get_mut();
+    ///     lock.counter += 1;
+    ///     panic!("Poisoning the state");
+    ///   })
+    ///   .join()
+    ///   .unwrap_err();
+    ///
+    ///   assert_eq!(state.get_mut().counter, 0);
+    /// }
+    /// ```
+    pub fn reset_to_default_on_poison(&self)
+    where
+        T: Default,
+    {
+        self.inner.poison.set_reset(|value| *value = T::default());
+        self.inner.poison.set_policy(PoisonPolicy::ResetToDefault);
+    }
+
+    /// Updates the state using `f` if its version still is `expected_version`.
+    /// Returns [`StateError::VersionMismatch`] without calling `f` if the state
+    /// has been changed in the meantime. This allows computing a change without
+    /// holding the lock, while detecting concurrent changes instead of overwriting them.
+    /// The version is incremented once `f` has been called.
+    /// Poisoned states are handled like in [`MutAppState::get_mut_checked`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// use app_state::{MutAppState, AppStateTrait, StateError};
+    ///
+    /// #[derive(Clone, Default)]
+    /// struct Document {
+    ///   text: String,
+    /// }
+    ///
+    /// fn main() {
+    ///   let state = MutAppState::<Document>::get_or_insert_default();
+    ///   let (version, document) = {
+    ///     let lock = state.get_mut();
+    ///     (lock.version(), lock.clone())
+    ///   };
+    ///
+    ///   let text = format!("{}Hello", document.text);
+    ///   state.get_mut().text = "Concurrent change".to_string();
+    ///
+    ///   let res = state.compare_and_update(version, |document| document.text = text);
+    ///   assert!(matches!(res, Err(StateError::VersionMismatch { .. })));
+    ///   assert_eq!(state.get_mut().text, "Concurrent change");
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn compare_and_update<R, F: FnOnce(&mut T) -> R>(
+        &self,
+        expected_version: u64,
+        f: F,
+    ) -> Result<R, StateError> {
+        let mut lock = MutAppStateLock::lock_checked(self)?;
+        let actual = self.version();
+        if actual != expected_version {
+            return Err(StateError::version_mismatch::<T>(expected_version, actual));
+        }
+
+        Ok(f(&mut lock))
+    }
+
+    /// Calls `callback` with the new value of this state whenever a lock
+    /// which mutably dereferenced the state is released.
+    /// Returns a handle which may be used to cancel the subscription.
+    ///
+    /// The callback is called on the thread releasing the lock, while the state
+    /// is still locked, hence it must not lock the state itself.
+    /// Changes made through [`MutAppStateLock::into_inner`] or by locking
+    /// the underlying mutex directly are not noticed.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use app_state::{MutAppState, AppStateTrait};
+    /// use std::sync::atomic::{AtomicU32, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// #[derive(Default)]
+    /// struct MyState {
+    ///   counter: u32,
+    /// }
+    ///
+    /// fn main() {
+    ///   let state = MutAppState::<MyState>::get_or_insert_default();
+    ///   let seen = Arc::new(AtomicU32::new(0));
+    ///   let seen_clone = seen.clone();
+    ///   let subscription = state.subscribe(move |state| {
+    ///     seen_clone.store(state.counter, Ordering::Relaxed);
+    ///   });
+    ///
+    ///   state.get_mut().counter += 1;
+    ///   assert_eq!(seen.load(Ordering::Relaxed), 1);
+    ///
+    ///   subscription.unsubscribe();
+    ///   state.get_mut().counter += 1;
+    ///   assert_eq!(seen.load(Ordering::Relaxed), 1);
+    /// }
+    /// ```
+    pub fn subscribe<F>(&self, callback: F) -> Subscription<T>
+    where
+        F: Fn(&T) + Send + Sync + 'static,
+    {
+        self.inner.subscribers.subscribe(Arc::new(callback))
+    }
+
+    /// Clears the poisoned state of this state, keeping its current value.
+    pub fn clear_poison(&self) {
+        self.inner.mutex.clear_poison();
+    }
+
+    /// Returns an identifier of this state, shared by all of its handles.
+    pub(crate) fn id(&self) -> usize {
+        Arc::as_ptr(&self.inner) as *const () as usize
+    }
+
+    /// Handles the poisoned lock `guard` according to the poison policy of this state.
+    pub(crate) fn recover<'a>(
+        &self,
+        mut guard: MutexGuard<'a, T>,
+    ) -> Result<MutexGuard<'a, T>, StateError> {
+        match self.inner.poison.policy() {
+            PoisonPolicy::Panic => {
+                drop(guard);
+                panic!("{}", StateError::poisoned::<T>())
+            }
+            PoisonPolicy::Recover => {
+                #[cfg(feature = "log")]
+                log::warn!("Recovering poisoned state {}", std::any::type_name::<T>());
+
+                self.inner.mutex.clear_poison();
+                Ok(guard)
+            }
+            PoisonPolicy::ResetToDefault => {
+                if !self.inner.poison.reset(&mut guard) {
+                    drop(guard);
+                    panic!(
+                        "Cannot reset poisoned state {} to its default value, as no default value has been registered",
+                        std::any::type_name::<T>()
+                    );
+                }
+
+                #[cfg(feature = "log")]
+                log::warn!(
+                    "Resetting poisoned state {} to its default value",
+                    std::any::type_name::<T>()
+                );
+
+                self.inner.mutex.clear_poison();
+                Ok(guard)
+            }
+            PoisonPolicy::ReturnError => Err(StateError::poisoned::<T>()),
+        }
+    }
+
+    /// Removes the state from the state store and takes ownership of its value.
+    ///
+    /// Returns `None` if the state has not been initialized.
+    /// If there are no other handles to the state, the value is returned as `Ok`.
+    /// Otherwise, the state is still removed from the state store and the
+    /// remaining handle is returned as `Err`, which may be unwrapped
+    /// using `into_inner` once all other handles have been dropped.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use app_state::{MutAppState, AppStateTrait};
+    ///
+    /// struct MyState {
+    ///   counter: u32,
+    /// }
+    ///
+    /// fn main() {
+    ///   MutAppState::init(MyState { counter: 0 });
+    ///   let state = MutAppState::<MyState>::take();
+    ///
+    ///   assert_eq!(state.unwrap().ok().unwrap().counter, 0);
+    ///   assert!(MutAppState::<MyState>::try_get().is_err());
+    /// }
+    /// ```
+    pub fn take() -> Option<Result<T, MutAppState<T>>> {
+        with_current_store(|store| store.remove::<MutAppState<T>>()).map(|state| {
+            let inner = Arc::try_unwrap(state.inner).map_err(|inner| MutAppState { inner })?;
+            if Arc::strong_count(&inner.mutex) > 1 {
+                return Err(MutAppState {
+                    inner: Arc::new(inner),
+                });
+            }
+
+            let mutex = inner.mutex.clone();
+            drop(inner);
+            let mutex = Arc::into_inner(mutex).expect("the mutex is only owned by this state");
+            Ok(mutex.into_inner().unwrap_or_else(PoisonError::into_inner))
+        })
     }
 }
 
 impl<T: 'static + Send> CreateAppState<T> for MutAppState<T> {
     fn new(state: T) -> MutAppState<T> {
-        MutAppState(Arc::new(Mutex::new(state)))
+        MutAppState::from(Arc::new(Mutex::new(state)))
+    }
+
+    /// Creates the state from the default value of `T`,
+    /// which is used by [`PoisonPolicy::ResetToDefault`].
+    fn new_default() -> MutAppState<T>
+    where
+        T: Default,
+    {
+        let state = MutAppState::new(T::default());
+        state.inner.poison.set_reset(|value| *value = T::default());
+        state
+    }
+
+    fn set_metrics_name(&self, name: &str) {
+        self.set_metrics_key(StatsKey::of::<MutAppState<T>, T>(Some(name)));
     }
 }
 
 impl<T: 'static + Send> AppStateTrait<T, MutAppState<T>> for MutAppState<T> {}
 
 impl<T: ?Sized> MutAppState<T> {
+    pub(crate) fn subscribers(&self) -> &Subscribers<T> {
+        &self.inner.subscribers
+    }
+
+    /// Returns the version of this state, which starts at zero and is incremented
+    /// whenever a lock which mutably dereferenced the state is released.
+    /// Use [`MutAppStateLock::version`] in order to read the version
+    /// consistently with the value of the state.
+    pub fn version(&self) -> u64 {
+        self.inner.version.load(Ordering::Acquire)
+    }
+
+    /// Records the metrics of locks of this state for `key`
+    /// instead of the key of an unnamed `MutAppState<T>`.
+    pub(crate) fn set_metrics_key(&self, key: StatsKey) {
+        self.inner.lock_key.set(key);
+    }
+
+    /// Starts timing a lock of this state.
+    pub(crate) fn lock_timing(&self) -> LockTiming {
+        LockTiming::start(&self.inner.lock_key, || {
+            StatsKey::of::<MutAppState<T>, T>(None)
+        })
+    }
+
+    /// Records a change of the state. Must be called while holding the lock.
+    pub(crate) fn increment_version(&self) {
+        self.inner.version.fetch_add(1, Ordering::AcqRel);
+    }
+
     /// Unwraps to the internal `Arc<T>`
     pub fn into_inner(self) -> Arc<Mutex<T>> {
-        self.0
+        self.inner.mutex.clone()
     }
 }
 
@@ -48,18 +444,38 @@ impl<T: ?Sized> Deref for MutAppState<T> {
     type Target = Arc<Mutex<T>>;
 
     fn deref(&self) -> &Arc<Mutex<T>> {
-        &self.0
+        &self.inner.mutex
     }
 }
 
 impl<T: ?Sized> Clone for MutAppState<T> {
     fn clone(&self) -> MutAppState<T> {
-        MutAppState(self.0.clone())
+        MutAppState {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for MutAppState<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("MutAppState")
+            .field(&self.inner.mutex)
+            .finish()
     }
 }
 
+/// Wraps the given mutex in a new state. The poison policy, the subscribers
+/// and the version are not shared with other states wrapping the same mutex.
 impl<T: ?Sized> From<Arc<Mutex<T>>> for MutAppState<T> {
     fn from(arc: Arc<Mutex<T>>) -> Self {
-        MutAppState(arc)
+        MutAppState {
+            inner: Arc::new(Inner {
+                poison: PoisonHandling::new(),
+                subscribers: Arc::new(Subscribers::new()),
+                version: AtomicU64::new(0),
+                lock_key: LockKey::new(),
+                mutex: arc,
+            }),
+        }
     }
 }
@@ -0,0 +1,84 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// The file format of a [`PersistentMutAppState`](crate::PersistentMutAppState)
+/// or a [`ConfigState`](crate::ConfigState).
+///
+/// Implemented by [`JsonFormat`], `TomlFormat` (requires the `toml` feature),
+/// `YamlFormat` (requires the `yaml` feature) and `BincodeFormat`
+/// (requires the `bincode` feature).
+/// Implement this trait in order to use any other format.
+pub trait PersistFormat {
+    /// Serializes `value` into the contents of a file.
+    fn to_bytes<T: Serialize>(value: &T) -> Result<Vec<u8>, String>;
+
+    /// Deserializes the contents of a file.
+    fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String>;
+}
+
+/// Stores states as pretty-printed JSON.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonFormat;
+
+impl PersistFormat for JsonFormat {
+    fn to_bytes<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+        serde_json::to_vec_pretty(value).map_err(|err| err.to_string())
+    }
+
+    fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+        serde_json::from_slice(bytes).map_err(|err| err.to_string())
+    }
+}
+
+/// Stores states as TOML.
+#[cfg(feature = "toml")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TomlFormat;
+
+#[cfg(feature = "toml")]
+impl PersistFormat for TomlFormat {
+    fn to_bytes<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+        toml::to_string_pretty(value)
+            .map(String::into_bytes)
+            .map_err(|err| err.to_string())
+    }
+
+    fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+        let text = std::str::from_utf8(bytes).map_err(|err| err.to_string())?;
+        toml::from_str(text).map_err(|err| err.to_string())
+    }
+}
+
+/// Stores states in the binary format of `bincode`.
+#[cfg(feature = "bincode")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BincodeFormat;
+
+#[cfg(feature = "bincode")]
+impl PersistFormat for BincodeFormat {
+    fn to_bytes<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+        bincode::serialize(value).map_err(|err| err.to_string())
+    }
+
+    fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+        bincode::deserialize(bytes).map_err(|err| err.to_string())
+    }
+}
+
+/// Stores states as YAML.
+#[cfg(feature = "yaml")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct YamlFormat;
+
+#[cfg(feature = "yaml")]
+impl PersistFormat for YamlFormat {
+    fn to_bytes<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+        serde_yaml::to_string(value)
+            .map(String::into_bytes)
+            .map_err(|err| err.to_string())
+    }
+
+    fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+        serde_yaml::from_slice(bytes).map_err(|err| err.to_string())
+    }
+}
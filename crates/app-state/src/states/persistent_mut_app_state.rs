@@ -0,0 +1,284 @@
+use crate::states::metrics::{self, StatsKey};
+use crate::states::store::with_current_store;
+use crate::states::traits::CreateAppState;
+use crate::{
+    MutAppState, MutAppStateLock, PersistFormat, PersistentMutAppStateLock, StateError,
+    StateLifecycle,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use std::thread;
+use std::time::Duration;
+
+/// Determines when a [`PersistentMutAppState`] is written back to its file.
+/// Regardless of the mode, modified states are written on [`shutdown`](crate::shutdown),
+/// when [`PersistentMutAppState::save`] is called and when the last handle is dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveMode {
+    /// Write the state whenever a lock which modified it is released.
+    OnRelease,
+    /// Write the state periodically from a background thread if it has been modified.
+    Interval(Duration),
+    /// Only write the state on shutdown.
+    OnShutdown,
+}
+
+pub(crate) struct Persisted<T: 'static + Send> {
+    state: MutAppState<T>,
+    path: PathBuf,
+    save_mode: SaveMode,
+    to_bytes: fn(&T) -> Result<Vec<u8>, String>,
+    dirty: AtomicBool,
+    last_error: Mutex<Option<StateError>>,
+}
+
+impl<T: 'static + Send> Persisted<T> {
+    pub(crate) fn save_mode(&self) -> SaveMode {
+        self.save_mode
+    }
+
+    pub(crate) fn state(&self) -> &MutAppState<T> {
+        &self.state
+    }
+
+    pub(crate) fn mark_dirty(&self) {
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Writes `value` to the file of the state.
+    /// The file is replaced atomically by writing to a temporary file first.
+    pub(crate) fn write(&self, value: &T) -> Result<(), StateError> {
+        let bytes = (self.to_bytes)(value).map_err(StateError::serialization::<T>)?;
+
+        let mut file_name = self.path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(".tmp");
+        let tmp_path = self.path.with_file_name(file_name);
+
+        let write = || -> std::io::Result<()> {
+            let mut file = fs::File::create(&tmp_path)?;
+            file.write_all(&bytes)?;
+            file.sync_all()?;
+            fs::rename(&tmp_path, &self.path)
+        };
+        write().map_err(|err| StateError::Persistence {
+            path: self.path.clone(),
+            message: err.to_string(),
+        })?;
+
+        self.dirty.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn save(&self) -> Result<(), StateError> {
+        let lock = MutAppStateLock::lock_checked(&self.state)?;
+        self.write(&lock)
+    }
+
+    /// Saves the state if it has been modified since it has last been written.
+    fn save_if_dirty(&self) {
+        if self.dirty.load(Ordering::Relaxed) {
+            let res = self.save();
+            self.record(res);
+        }
+    }
+
+    /// Records the result of writing the state in the background.
+    pub(crate) fn record(&self, res: Result<(), StateError>) {
+        if let Err(err) = res {
+            #[cfg(feature = "log")]
+            log::error!("Could not save state: {}", err);
+
+            *self
+                .last_error
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = Some(err);
+        }
+    }
+}
+
+impl<T: 'static + Send> Drop for Persisted<T> {
+    fn drop(&mut self) {
+        self.save_if_dirty();
+    }
+}
+
+/// A mutable app state which is loaded from a file and written back to it.
+///
+/// The state is loaded from its file on initialization, falling back to the
+/// default value of `T` if the file does not exist. Modifications are written
+/// back according to the [`SaveMode`] of the state. Files are replaced atomically
+/// by writing to a temporary file next to them and renaming it afterwards.
+/// The file format is determined by a [`PersistFormat`], e.g. [`JsonFormat`](crate::JsonFormat).
+///
+/// Only modifications made through [`PersistentMutAppState::get_mut`] are tracked.
+/// Errors while writing the state in the background are logged
+/// and can be retrieved using [`PersistentMutAppState::last_error`].
+///
+/// # Examples
+/// ```rust
+/// use app_state::{JsonFormat, PersistentMutAppState, SaveMode};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize)]
+/// struct Settings {
+///   volume: u32,
+/// }
+///
+/// fn main() {
+///   let path = std::env::temp_dir().join("app-state-settings-example.json");
+///   let settings = PersistentMutAppState::<Settings>::init::<JsonFormat>(
+///     &path,
+///     SaveMode::OnRelease,
+///   )
+///   .unwrap();
+///
+///   settings.get_mut().volume = 10;
+///   assert!(std::fs::read_to_string(&path).unwrap().contains("10"));
+///   # std::fs::remove_file(&path).unwrap();
+/// }
+/// ```
+pub struct PersistentMutAppState<T: 'static + Send>(Arc<Persisted<T>>);
+
+impl<T> PersistentMutAppState<T>
+where
+    T: 'static + Send + Serialize + DeserializeOwned + Default,
+{
+    /// Loads the state from the file at `path` without adding it to the state store.
+    /// If the file does not exist, the default value of `T` is used.
+    /// Returns `Err` if the file exists, but could not be read or deserialized.
+    pub fn open<F: PersistFormat>(
+        path: impl Into<PathBuf>,
+        save_mode: SaveMode,
+    ) -> Result<PersistentMutAppState<T>, StateError> {
+        let path = path.into();
+        let value = match fs::read(&path) {
+            Ok(bytes) => F::from_bytes::<T>(&bytes).map_err(StateError::deserialization::<T>)?,
+            Err(err) if err.kind() == ErrorKind::NotFound => T::default(),
+            Err(err) => {
+                return Err(StateError::Persistence {
+                    path,
+                    message: err.to_string(),
+                })
+            }
+        };
+
+        let state = MutAppState::new(value);
+        state.set_metrics_key(StatsKey::of::<PersistentMutAppState<T>, T>(None));
+
+        let persisted = Arc::new(Persisted {
+            state,
+            path,
+            save_mode,
+            to_bytes: F::to_bytes::<T>,
+            dirty: AtomicBool::new(false),
+            last_error: Mutex::new(None),
+        });
+
+        if let SaveMode::Interval(interval) = save_mode {
+            spawn_saver(Arc::downgrade(&persisted), interval);
+        }
+
+        Ok(PersistentMutAppState(persisted))
+    }
+
+    /// Loads the state from the file at `path` and initializes it in the state store.
+    /// If the state has already been initialized, this will overwrite the existing state.
+    /// See [`PersistentMutAppState::open`] for details.
+    pub fn init<F: PersistFormat>(
+        path: impl Into<PathBuf>,
+        save_mode: SaveMode,
+    ) -> Result<PersistentMutAppState<T>, StateError> {
+        let state = Self::open::<F>(path, save_mode)?;
+        with_current_store(|store| store.insert_with_lifecycle(state.clone()));
+
+        Ok(state)
+    }
+}
+
+impl<T: 'static + Send> PersistentMutAppState<T> {
+    /// Returns the state from the state store.
+    /// If the state has not been initialized, this will panic.
+    pub fn get() -> PersistentMutAppState<T> {
+        metrics::record_get::<PersistentMutAppState<T>, T>(None);
+        with_current_store(|store| store.get())
+    }
+
+    /// Returns the state from the state store.
+    /// If the state has not been initialized, this will return `Err`.
+    pub fn try_get() -> Result<PersistentMutAppState<T>, StateError> {
+        metrics::record_get::<PersistentMutAppState<T>, T>(None);
+        with_current_store(|store| store.try_get())
+    }
+
+    /// Locks the state. Modifying the state through the returned lock
+    /// marks the state as modified.
+    /// Poisoned states are handled like in [`MutAppState::get_mut`].
+    #[track_caller]
+    pub fn get_mut(&self) -> PersistentMutAppStateLock<'_, T> {
+        PersistentMutAppStateLock::new(&self.0)
+    }
+
+    /// Writes the state to its file, regardless of whether it has been modified.
+    pub fn save(&self) -> Result<(), StateError> {
+        self.0.save()
+    }
+
+    /// Returns the path of the file the state is stored in.
+    pub fn path(&self) -> &Path {
+        &self.0.path
+    }
+
+    /// Returns the [`SaveMode`] of the state.
+    pub fn save_mode(&self) -> SaveMode {
+        self.0.save_mode
+    }
+
+    /// Whether the state has been modified since it has last been written.
+    pub fn is_dirty(&self) -> bool {
+        self.0.dirty.load(Ordering::Relaxed)
+    }
+
+    /// Returns the last error which occurred while writing the state
+    /// in the background and resets it.
+    pub fn last_error(&self) -> Option<StateError> {
+        self.0
+            .last_error
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+    }
+}
+
+impl<T: 'static + Send> StateLifecycle for PersistentMutAppState<T> {
+    fn on_shutdown(&self) {
+        self.0.save_if_dirty();
+    }
+}
+
+impl<T: 'static + Send> Clone for PersistentMutAppState<T> {
+    fn clone(&self) -> Self {
+        PersistentMutAppState(self.0.clone())
+    }
+}
+
+fn spawn_saver<T: 'static + Send>(persisted: Weak<Persisted<T>>, interval: Duration) {
+    let res = thread::Builder::new()
+        .name("app-state-persist".to_string())
+        .spawn(move || loop {
+            thread::sleep(interval);
+            match persisted.upgrade() {
+                Some(persisted) => persisted.save_if_dirty(),
+                None => break,
+            }
+        });
+
+    if let Err(_err) = res {
+        #[cfg(feature = "log")]
+        log::error!("Could not start saving state periodically: {}", _err);
+    }
+}
@@ -0,0 +1,57 @@
+use crate::states::persistent_mut_app_state::Persisted;
+use crate::{MutAppStateLock, SaveMode};
+use std::ops::{Deref, DerefMut};
+use std::thread;
+
+/// The lock guard for a persistent mutable app state.
+/// When this guard is dropped after the state has been modified through it,
+/// the state is marked as modified and written back to its file
+/// if its save mode is [`SaveMode::OnRelease`].
+/// Changes are not persisted if the guard is dropped while panicking.
+pub struct PersistentMutAppStateLock<'a, T: 'static + Send> {
+    lock: MutAppStateLock<'a, T>,
+    persisted: &'a Persisted<T>,
+    modified: bool,
+}
+
+impl<'a, T: 'static + Send> PersistentMutAppStateLock<'a, T> {
+    #[track_caller]
+    pub(crate) fn new(persisted: &'a Persisted<T>) -> PersistentMutAppStateLock<'a, T> {
+        PersistentMutAppStateLock {
+            lock: MutAppStateLock::new(persisted.state()),
+            persisted,
+            modified: false,
+        }
+    }
+}
+
+impl<'a, T: 'static + Send> Deref for PersistentMutAppStateLock<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.lock
+    }
+}
+
+impl<'a, T: 'static + Send> DerefMut for PersistentMutAppStateLock<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.modified = true;
+        &mut self.lock
+    }
+}
+
+impl<'a, T: 'static + Send> Drop for PersistentMutAppStateLock<'a, T> {
+    fn drop(&mut self) {
+        // Changes made before a panic may be incomplete, hence they are not persisted
+        if !self.modified || thread::panicking() {
+            return;
+        }
+
+        self.persisted.mark_dirty();
+        if self.persisted.save_mode() == SaveMode::OnRelease {
+            // Write while holding the lock, so that writes happen in order
+            let res = self.persisted.write(&self.lock);
+            self.persisted.record(res);
+        }
+    }
+}
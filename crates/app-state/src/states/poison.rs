@@ -0,0 +1,126 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+static DEFAULT_POLICY: AtomicU8 = AtomicU8::new(PoisonPolicy::ReturnError as u8);
+
+/// Determines how a `MutAppState` behaves when it is locked
+/// after a thread panicked while holding the lock.
+///
+/// The policy can be set for all states using [`set_default_poison_policy`]
+/// or for a single state using `MutAppState::set_poison_policy`.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{MutAppState, AppStateTrait, PoisonPolicy};
+///
+/// #[derive(Default)]
+/// struct MyState {
+///   counter: u32,
+/// }
+///
+/// fn main() {
+///   let state = MutAppState::<MyState>::get_or_insert_default();
+///   state.set_poison_policy(PoisonPolicy::Recover);
+///
+///   let cloned = state.clone();
+///   std::thread::spawn(move || {
+///     let mut lock = cloned.get_mut();
+///     lock.counter += 1;
+///     panic!("Poisoning the state");
+///   })
+///   .join()
+///   .unwrap_err();
+///
+///   assert_eq!(state.get_mut().counter, 1);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum PoisonPolicy {
+    /// Panic whenever the poisoned state is locked,
+    /// including `MutAppState::get_mut_checked` and `MutAppState::try_get_mut`.
+    Panic,
+    /// Clear the poison and continue using the current value of the state.
+    Recover,
+    /// Clear the poison and reset the state to its default value.
+    /// The default value is registered for states created using
+    /// `get_or_insert_default` or `init_default_mut_state` and by
+    /// `MutAppState::reset_to_default_on_poison`. Setting this policy on a
+    /// state without a registered default value panics, as does locking
+    /// such a state while it is poisoned if this is the default policy.
+    ResetToDefault,
+    /// Return [`StateError::Poisoned`](crate::StateError::Poisoned) from
+    /// fallible functions and panic in all other functions.
+    /// This is the default policy.
+    #[default]
+    ReturnError,
+}
+
+impl PoisonPolicy {
+    fn from_u8(value: u8) -> Option<PoisonPolicy> {
+        match value {
+            0 => Some(PoisonPolicy::Panic),
+            1 => Some(PoisonPolicy::Recover),
+            2 => Some(PoisonPolicy::ResetToDefault),
+            3 => Some(PoisonPolicy::ReturnError),
+            _ => None,
+        }
+    }
+}
+
+/// Sets the poison policy of all states which do not have their own policy.
+pub fn set_default_poison_policy(policy: PoisonPolicy) {
+    DEFAULT_POLICY.store(policy as u8, Ordering::Relaxed);
+}
+
+/// Returns the poison policy of all states which do not have their own policy.
+pub fn default_poison_policy() -> PoisonPolicy {
+    PoisonPolicy::from_u8(DEFAULT_POLICY.load(Ordering::Relaxed)).unwrap_or_default()
+}
+
+/// The poison handling of a single state, shared by all of its handles.
+pub(crate) struct PoisonHandling<T: ?Sized> {
+    policy: AtomicU8,
+    reset: OnceLock<fn(&mut T)>,
+}
+
+impl<T: ?Sized> PoisonHandling<T> {
+    const UNSET: u8 = u8::MAX;
+
+    pub(crate) fn new() -> PoisonHandling<T> {
+        PoisonHandling {
+            policy: AtomicU8::new(Self::UNSET),
+            reset: OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn policy(&self) -> PoisonPolicy {
+        PoisonPolicy::from_u8(self.policy.load(Ordering::Relaxed))
+            .unwrap_or_else(default_poison_policy)
+    }
+
+    pub(crate) fn set_policy(&self, policy: PoisonPolicy) {
+        self.policy.store(policy as u8, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_reset(&self, reset: fn(&mut T)) {
+        // The reset function is the same for all values of the same type
+        let _ = self.reset.set(reset);
+    }
+
+    pub(crate) fn has_reset(&self) -> bool {
+        self.reset.get().is_some()
+    }
+
+    /// Resets `value` to its default value.
+    /// Returns `false` if no default value has been registered.
+    pub(crate) fn reset(&self, value: &mut T) -> bool {
+        match self.reset.get() {
+            Some(reset) => {
+                reset(value);
+                true
+            }
+            None => false,
+        }
+    }
+}
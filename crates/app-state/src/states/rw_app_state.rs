@@ -0,0 +1,72 @@
+use crate::states::traits::CreateAppState;
+use crate::{AppStateTrait, RwAppStateReadLock, RwAppStateWriteLock};
+use std::ops::Deref;
+use std::sync::{Arc, RwLock};
+
+/// A mutable app state backed by a reader-writer lock.
+/// Any number of threads may read the state at the same time,
+/// while writing to the state requires exclusive access.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{RwAppState, AppStateTrait, stateful};
+///
+/// struct MyState {
+///   counter: u32,
+/// }
+///
+/// #[stateful]
+/// fn func(state: RwAppState<MyState>) {
+///   println!("Counter: {}", state.read().counter);
+///   state.write().counter += 1;
+/// }
+/// ```
+#[derive(Debug)]
+pub struct RwAppState<T: ?Sized>(Arc<RwLock<T>>);
+
+impl<T: 'static + Send + Sync> RwAppState<T> {
+    /// Locks the state for reading.
+    pub fn read(&self) -> RwAppStateReadLock<'_, T> {
+        RwAppStateReadLock::new(self)
+    }
+
+    /// Locks the state for writing.
+    pub fn write(&self) -> RwAppStateWriteLock<'_, T> {
+        RwAppStateWriteLock::new(self)
+    }
+}
+
+impl<T: 'static + Send + Sync> CreateAppState<T> for RwAppState<T> {
+    fn new(state: T) -> RwAppState<T> {
+        RwAppState(Arc::new(RwLock::new(state)))
+    }
+}
+
+impl<T: 'static + Send + Sync> AppStateTrait<T, RwAppState<T>> for RwAppState<T> {}
+
+impl<T: ?Sized> RwAppState<T> {
+    /// Unwraps to the internal `Arc<RwLock<T>>`
+    pub fn into_inner(self) -> Arc<RwLock<T>> {
+        self.0
+    }
+}
+
+impl<T: ?Sized> Deref for RwAppState<T> {
+    type Target = Arc<RwLock<T>>;
+
+    fn deref(&self) -> &Arc<RwLock<T>> {
+        &self.0
+    }
+}
+
+impl<T: ?Sized> Clone for RwAppState<T> {
+    fn clone(&self) -> RwAppState<T> {
+        RwAppState(self.0.clone())
+    }
+}
+
+impl<T: ?Sized> From<Arc<RwLock<T>>> for RwAppState<T> {
+    fn from(arc: Arc<RwLock<T>>) -> Self {
+        RwAppState(arc)
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{RwAppState, StateError};
+use std::ops::{Deref, DerefMut};
+use std::sync::{RwLockReadGuard, RwLockWriteGuard};
+
+/// The read lock guard for a reader-writer app state.
+/// This is a wrapper around `RwLockReadGuard`.
+/// When this guard is dropped, the lock will be released.
+/// Other threads may read the state while this guard is held,
+/// but no thread can write to the state until this guard is dropped.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{RwAppState, RwAppStateReadLock, AppStateTrait, stateful};
+///
+/// struct MyState {
+///   counter: u32,
+/// }
+///
+/// #[stateful]
+/// fn func(state: RwAppStateReadLock<MyState>) {
+///   println!("Counter: {}", state.counter);
+/// }
+/// ```
+pub struct RwAppStateReadLock<'a, T: ?Sized>(RwLockReadGuard<'a, T>);
+
+impl<'a, T: 'static + Send + Sync> RwAppStateReadLock<'a, T> {
+    /// Locks the given state for reading.
+    /// If the state has been poisoned, this will panic.
+    pub fn new(inner: &'a RwAppState<T>) -> RwAppStateReadLock<'a, T> {
+        match Self::try_new(inner) {
+            Ok(lock) => lock,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Locks the given state for reading.
+    /// If the state has been poisoned, this will return `Err`.
+    pub fn try_new(inner: &'a RwAppState<T>) -> Result<RwAppStateReadLock<'a, T>, StateError> {
+        RwAppState::deref(inner)
+            .read()
+            .map(RwAppStateReadLock)
+            .map_err(|_| StateError::poisoned::<T>())
+    }
+}
+
+impl<'a, T: ?Sized> RwAppStateReadLock<'a, T> {
+    /// Returns reference to inner `T`.
+    pub fn get_ref(&self) -> &RwLockReadGuard<'a, T> {
+        &self.0
+    }
+
+    /// Unwraps to the internal `RwLockReadGuard<T>`
+    pub fn into_inner(self) -> RwLockReadGuard<'a, T> {
+        self.0
+    }
+}
+
+impl<'a, T: ?Sized> Deref for RwAppStateReadLock<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// The write lock guard for a reader-writer app state.
+/// This is a wrapper around `RwLockWriteGuard`.
+/// When this guard is dropped, the lock will be released.
+/// As this locks the state, no other thread can access the state until this guard is dropped.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{RwAppState, RwAppStateWriteLock, AppStateTrait, stateful};
+///
+/// struct MyState {
+///   counter: u32,
+/// }
+///
+/// #[stateful]
+/// fn func(mut state: RwAppStateWriteLock<MyState>) {
+///   state.counter += 1;
+/// }
+/// ```
+pub struct RwAppStateWriteLock<'a, T: ?Sized>(RwLockWriteGuard<'a, T>);
+
+impl<'a, T: 'static + Send + Sync> RwAppStateWriteLock<'a, T> {
+    /// Locks the given state for writing.
+    /// If the state has been poisoned, this will panic.
+    pub fn new(inner: &'a RwAppState<T>) -> RwAppStateWriteLock<'a, T> {
+        match Self::try_new(inner) {
+            Ok(lock) => lock,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Locks the given state for writing.
+    /// If the state has been poisoned, this will return `Err`.
+    pub fn try_new(inner: &'a RwAppState<T>) -> Result<RwAppStateWriteLock<'a, T>, StateError> {
+        RwAppState::deref(inner)
+            .write()
+            .map(RwAppStateWriteLock)
+            .map_err(|_| StateError::poisoned::<T>())
+    }
+}
+
+impl<'a, T: ?Sized> RwAppStateWriteLock<'a, T> {
+    /// Returns reference to inner `T`.
+    pub fn get_ref(&self) -> &RwLockWriteGuard<'a, T> {
+        &self.0
+    }
+
+    /// Unwraps to the internal `RwLockWriteGuard<T>`
+    pub fn into_inner(self) -> RwLockWriteGuard<'a, T> {
+        self.0
+    }
+}
+
+impl<'a, T: ?Sized> Deref for RwAppStateWriteLock<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<'a, T: ?Sized> DerefMut for RwAppStateWriteLock<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
@@ -0,0 +1,276 @@
+use crate::states::store::with_current_store;
+use crate::states::traits::CreateAppState;
+use crate::{
+    AppState, MutAppState, MutAppStateLock, RwAppState, StateError, StateStore, SwapAppState,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::any::TypeId;
+use std::collections::BTreeMap;
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// A state which can be included in a [`Snapshot`].
+///
+/// Implemented for `AppState`, `MutAppState`, `RwAppState` and `SwapAppState`
+/// holding a value which implements `Serialize` and `DeserializeOwned`.
+/// Mutable states are locked while they are serialized.
+pub trait SerializableState: 'static + Clone + Send {
+    /// The type of the value held by the state.
+    type Value: 'static + Serialize + DeserializeOwned;
+
+    /// Serializes the value of the state.
+    fn serialize_value(&self) -> Result<serde_json::Value, StateError>;
+
+    /// Creates a new state holding `value`.
+    fn from_value(value: Self::Value) -> Self;
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<serde_json::Value, StateError> {
+    serde_json::to_value(value).map_err(|err| StateError::serialization::<T>(err.to_string()))
+}
+
+impl<T> SerializableState for AppState<T>
+where
+    T: 'static + Send + Sync + Serialize + DeserializeOwned,
+{
+    type Value = T;
+
+    fn serialize_value(&self) -> Result<serde_json::Value, StateError> {
+        to_json(self.get_ref())
+    }
+
+    fn from_value(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> SerializableState for MutAppState<T>
+where
+    T: 'static + Send + Serialize + DeserializeOwned,
+{
+    type Value = T;
+
+    fn serialize_value(&self) -> Result<serde_json::Value, StateError> {
+        to_json(&*MutAppStateLock::lock_checked(self)?)
+    }
+
+    fn from_value(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> SerializableState for RwAppState<T>
+where
+    T: 'static + Send + Sync + Serialize + DeserializeOwned,
+{
+    type Value = T;
+
+    fn serialize_value(&self) -> Result<serde_json::Value, StateError> {
+        let lock: &RwLock<T> = self;
+        let value = lock
+            .read()
+            .map_err(|_: PoisonError<_>| StateError::poisoned::<T>())?;
+        to_json(&*value)
+    }
+
+    fn from_value(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> SerializableState for SwapAppState<T>
+where
+    T: 'static + Send + Sync + Serialize + DeserializeOwned,
+{
+    type Value = T;
+
+    fn serialize_value(&self) -> Result<serde_json::Value, StateError> {
+        to_json(&*self.load())
+    }
+
+    fn from_value(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+type Restore = Box<dyn FnOnce(&StateStore)>;
+
+/// Serializes and restores a single state.
+pub(crate) struct StateSerializer {
+    state: TypeId,
+    type_name: &'static str,
+    serialize: fn(&StateStore) -> Result<Option<serde_json::Value>, StateError>,
+    restore: fn(&serde_json::Value) -> Result<Restore, StateError>,
+}
+
+impl StateSerializer {
+    pub(crate) fn new<U: SerializableState>() -> StateSerializer {
+        StateSerializer {
+            state: TypeId::of::<U>(),
+            type_name: std::any::type_name::<U::Value>(),
+            serialize: |store| match store.try_get::<U>() {
+                Ok(state) => state.serialize_value().map(Some),
+                Err(_) => Ok(None),
+            },
+            restore: |value| {
+                let value = U::Value::deserialize(value)
+                    .map_err(|err| StateError::deserialization::<U::Value>(err.to_string()))?;
+
+                Ok(Box::new(move |store| {
+                    store.insert_state(None, U::from_value(value))
+                }))
+            },
+        }
+    }
+
+    /// Whether this serializer handles the same state as `other`.
+    pub(crate) fn same_state(&self, other: &StateSerializer) -> bool {
+        self.state == other.state
+    }
+
+    /// Whether this serializer handles a different kind of state holding the same type
+    /// as `other`, which can not be told apart in a snapshot.
+    pub(crate) fn conflicts_with(&self, other: &StateSerializer) -> bool {
+        self.type_name == other.type_name && !self.same_state(other)
+    }
+
+    /// Returns [`StateError::SerializationConflict`] if two of `serializers` conflict.
+    pub(crate) fn check_conflicts(serializers: &[Arc<StateSerializer>]) -> Result<(), StateError> {
+        for (i, serializer) in serializers.iter().enumerate() {
+            if serializers[i + 1..]
+                .iter()
+                .any(|other| other.conflicts_with(serializer))
+            {
+                return Err(StateError::SerializationConflict {
+                    type_name: serializer.type_name,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Serializes the state, returning `None` if it has not been initialized.
+    pub(crate) fn serialize(
+        &self,
+        store: &StateStore,
+    ) -> Result<Option<serde_json::Value>, StateError> {
+        (self.serialize)(store)
+    }
+
+    /// Deserializes the state, returning a function initializing it.
+    pub(crate) fn restore(&self, value: &serde_json::Value) -> Result<Restore, StateError> {
+        (self.restore)(value)
+    }
+}
+
+/// The serialized values of all serializable states,
+/// keyed by the [`type_name`](std::any::type_name) of the state values.
+///
+/// Created by [`snapshot`] and restored using [`restore`].
+/// The snapshot itself implements `Serialize` and `Deserialize`,
+/// hence it can be stored in any format supported by serde.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Snapshot {
+    states: BTreeMap<String, serde_json::Value>,
+}
+
+impl Snapshot {
+    /// Returns the serialized value of the states holding a `T`.
+    pub fn get<T: ?Sized>(&self) -> Option<&serde_json::Value> {
+        self.get_by_name(std::any::type_name::<T>())
+    }
+
+    /// Returns the serialized value of the states holding the type with the given type name.
+    pub fn get_by_name(&self, type_name: &str) -> Option<&serde_json::Value> {
+        self.states.get(type_name)
+    }
+
+    /// Returns the serialized values of all states, ordered by their type name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &serde_json::Value)> {
+        self.states
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    pub(crate) fn insert(&mut self, type_name: &str, value: serde_json::Value) {
+        self.states.insert(type_name.to_string(), value);
+    }
+}
+
+/// Registers the state `U` to be included in snapshots,
+/// for example `MutAppState<Settings>`.
+/// The derive macros register a state if the `app_state(serialize)` attribute is present.
+///
+/// If the current thread has been isolated using
+/// [`StateStore::isolated`](crate::StateStore::isolated),
+/// the state is only registered in the isolated store.
+///
+/// As snapshots are keyed by the type of the state values, every type can only be
+/// registered as a single kind of state. If e.g. both `AppState<Settings>` and
+/// `MutAppState<Settings>` are registered, [`snapshot`] and [`restore`] return
+/// [`StateError::SerializationConflict`]. This does not panic, as the derive macros
+/// register states before `main`, where a panic would abort the process.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{snapshot, AppState, AppStateTrait, InitAppState, InitMutAppState, MutAppState, StateError};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, InitAppState, InitMutAppState, Serialize, Deserialize)]
+/// #[app_state(serialize)]
+/// struct Settings {
+///   volume: u32,
+/// }
+///
+/// fn main() {
+///   assert!(matches!(snapshot(), Err(StateError::SerializationConflict { .. })));
+/// }
+/// ```
+pub fn register_serializable<U: SerializableState>() {
+    with_current_store(|store| store.register_serializable::<U>());
+}
+
+/// Serializes all states which have been registered using [`register_serializable`].
+/// States which have not been initialized are skipped.
+/// Named states are not included.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{register_serializable, restore, snapshot, AppStateTrait, MutAppState};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Settings {
+///   volume: u32,
+/// }
+///
+/// fn main() {
+///   register_serializable::<MutAppState<Settings>>();
+///   MutAppState::init(Settings { volume: 10 });
+///
+///   let json = serde_json::to_string(&snapshot().unwrap()).unwrap();
+///   MutAppState::<Settings>::get().get_mut().volume = 0;
+///
+///   restore(&serde_json::from_str(&json).unwrap()).unwrap();
+///   assert_eq!(MutAppState::<Settings>::get().get_mut().volume, 10);
+/// }
+/// ```
+pub fn snapshot() -> Result<Snapshot, StateError> {
+    with_current_store(|store| store.snapshot())
+}
+
+/// Initializes all states contained in `snapshot`, replacing existing states.
+/// Handles to the replaced states which are still held elsewhere are not updated.
+///
+/// All states are deserialized before any state is replaced. If the snapshot contains
+/// a state which has not been registered using [`register_serializable`],
+/// this returns [`StateError::UnknownState`] without replacing any state.
+pub fn restore(snapshot: &Snapshot) -> Result<(), StateError> {
+    with_current_store(|store| store.restore(snapshot))
+}
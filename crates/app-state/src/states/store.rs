@@ -0,0 +1,1081 @@
+use crate::states::factory::initialization_order;
+#[cfg(feature = "serde")]
+use crate::states::snapshot::{SerializableState, Snapshot, StateSerializer};
+use crate::states::traits::CreateAppState;
+#[cfg(feature = "tokio")]
+use crate::states::watch::{StateWatcher, Watcher};
+#[cfg(feature = "tokio")]
+use crate::MutAppState;
+use crate::{AppState, StateError, StateFactory, StateLifecycle};
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, ThreadId};
+
+static GLOBAL_STORE: StateStore = StateStore::new();
+
+thread_local! {
+    static ISOLATED_STORES: RefCell<Vec<Arc<StateStore>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Runs `f` with the store used by the current thread.
+/// This is the innermost isolated store of the current thread
+/// or the global store if the thread is not isolated.
+pub(crate) fn with_current_store<R, F: FnOnce(&StateStore) -> R>(f: F) -> R {
+    match ISOLATED_STORES.with(|stores| stores.borrow().last().cloned()) {
+        Some(store) => f(&store),
+        None => f(StateStore::global()),
+    }
+}
+
+/// A registry holding app states.
+///
+/// All functions of [`AppStateTrait`](crate::AppStateTrait) operate on the
+/// global store returned by [`StateStore::global`]. Additional stores can be
+/// created in order to keep independent sets of states in the same process,
+/// for example for multiple embedded app instances.
+///
+/// The states returned by a store are regular `AppState`s and `MutAppState`s
+/// and can be used just like the states obtained from the global store.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{AppState, MutAppState, StateStore};
+///
+/// struct MyState {
+///   counter: u32,
+/// }
+///
+/// fn main() {
+///   let store = StateStore::new();
+///   store.init::<AppState<_>, _>(MyState { counter: 0 });
+///   store.init::<MutAppState<_>, _>(MyState { counter: 1 });
+///
+///   let state = store.get::<AppState<MyState>>();
+///   assert_eq!(state.counter, 0);
+///
+///   let mut_state = store.get::<MutAppState<MyState>>();
+///   mut_state.get_mut().counter += 1;
+///   assert_eq!(mut_state.get_mut().counter, 2);
+/// }
+/// ```
+pub struct StateStore {
+    registry: Mutex<Registry>,
+    initialized: Condvar,
+    factories: Mutex<Vec<Arc<StateFactory>>>,
+    #[cfg(feature = "serde")]
+    serializers: Mutex<Vec<Arc<StateSerializer>>>,
+    parent: ParentStore,
+}
+
+/// The contents of a [`StateStore`], guarded by a single mutex.
+struct Registry {
+    states: Option<HashMap<StateKey, Box<dyn Any + Send>>>,
+    /// The states which are currently being initialized
+    /// and the threads initializing them.
+    initializing: Vec<(StateKey, ThreadId)>,
+    /// The threads waiting for another thread to initialize a state.
+    waiting: Vec<(ThreadId, StateKey)>,
+    /// The shutdown hooks of the states, in order of initialization.
+    lifecycle: Vec<(StateKey, ShutdownHook)>,
+    /// The watchers of the states, see `MutAppState::watch`.
+    #[cfg(feature = "tokio")]
+    watchers: Vec<(StateKey, Arc<dyn StateWatcher>)>,
+}
+
+type ShutdownHook = Arc<dyn Fn(&StateStore) + Send + Sync>;
+
+impl Registry {
+    const fn new() -> Registry {
+        Registry {
+            states: None,
+            initializing: Vec::new(),
+            waiting: Vec::new(),
+            lifecycle: Vec::new(),
+            #[cfg(feature = "tokio")]
+            watchers: Vec::new(),
+        }
+    }
+
+    fn initializer_of(&self, key: &StateKey) -> Option<ThreadId> {
+        self.initializing
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, thread)| *thread)
+    }
+
+    /// Returns `true` if `thread` is (indirectly) waiting for
+    /// a state which is being initialized by `target`.
+    fn waits_for(&self, mut thread: ThreadId, target: ThreadId) -> bool {
+        // Every thread waits for at most one state, hence
+        // the chain can be no longer than the number of waiting threads
+        for _ in 0..=self.waiting.len() {
+            let next = self
+                .waiting
+                .iter()
+                .find(|(t, _)| *t == thread)
+                .and_then(|(_, key)| self.initializer_of(key));
+
+            match next {
+                Some(next) if next == target => return true,
+                Some(next) => thread = next,
+                None => return false,
+            }
+        }
+
+        false
+    }
+}
+
+/// Marks a state as being initialized by the current thread.
+/// The marker is removed and all waiting threads are notified
+/// when this guard is dropped, even if the initializer panicked.
+struct InitializationGuard<'a> {
+    store: &'a StateStore,
+    key: StateKey,
+}
+
+impl Drop for InitializationGuard<'_> {
+    fn drop(&mut self) {
+        let mut registry = self.store.lock_registry();
+        registry.initializing.retain(|(key, _)| *key != self.key);
+        drop(registry);
+
+        self.store.initialized.notify_all();
+    }
+}
+
+/// The key of a state in a [`StateStore`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct StateKey {
+    type_id: TypeId,
+    name: Option<String>,
+}
+
+impl StateKey {
+    fn of<U: 'static>(name: Option<&str>) -> StateKey {
+        StateKey {
+            type_id: TypeId::of::<U>(),
+            name: name.map(str::to_string),
+        }
+    }
+}
+
+/// The store a [`StateStore`] falls back to if a state could not be found.
+enum ParentStore {
+    None,
+    Global,
+    Isolated(Arc<StateStore>),
+}
+
+impl ParentStore {
+    fn get(&self) -> Option<&StateStore> {
+        match self {
+            ParentStore::None => None,
+            ParentStore::Global => Some(StateStore::global()),
+            ParentStore::Isolated(store) => Some(store),
+        }
+    }
+}
+
+/// A guard isolating the states of the current thread.
+/// Created by [`StateStore::isolated`].
+///
+/// When this guard is dropped, all states initialized
+/// while the guard was active are dropped as well.
+pub struct IsolatedStateStore {
+    store: Arc<StateStore>,
+    // Isolation is bound to the current thread
+    _not_send: PhantomData<*const ()>,
+}
+
+impl StateStore {
+    /// Creates a new, empty state store.
+    pub const fn new() -> StateStore {
+        StateStore {
+            registry: Mutex::new(Registry::new()),
+            initialized: Condvar::new(),
+            factories: Mutex::new(Vec::new()),
+            #[cfg(feature = "serde")]
+            serializers: Mutex::new(Vec::new()),
+            parent: ParentStore::None,
+        }
+    }
+
+    /// Returns the global state store.
+    /// This is the store used by all functions of `AppStateTrait`,
+    /// unless the current thread has been isolated using [`StateStore::isolated`].
+    pub fn global() -> &'static StateStore {
+        &GLOBAL_STORE
+    }
+
+    /// Isolates the states of the current thread until the returned guard is dropped.
+    ///
+    /// While the guard is alive, all functions of `AppStateTrait` called on the
+    /// current thread operate on a new store overlaying the previously used store:
+    /// States are initialized in the new store only, while states which
+    /// do not exist in the new store are still looked up in the previously used store.
+    /// This allows tests to run in parallel while using the same state types.
+    /// Other threads, including threads spawned while the guard is alive,
+    /// are not affected.
+    ///
+    /// The [`test`](crate::test) attribute can be used to isolate a whole test.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use app_state::{AppState, AppStateTrait, StateStore};
+    ///
+    /// struct MyState {
+    ///   counter: u32,
+    /// }
+    ///
+    /// fn main() {
+    ///   let guard = StateStore::isolated();
+    ///   AppState::init(MyState { counter: 0 });
+    ///   assert!(AppState::<MyState>::try_get().is_ok());
+    ///
+    ///   drop(guard);
+    ///   assert!(AppState::<MyState>::try_get().is_err());
+    /// }
+    /// ```
+    pub fn isolated() -> IsolatedStateStore {
+        let store = ISOLATED_STORES.with(|stores| {
+            let mut stores = stores.borrow_mut();
+            let parent = match stores.last() {
+                Some(store) => ParentStore::Isolated(store.clone()),
+                None => ParentStore::Global,
+            };
+
+            let store = Arc::new(StateStore {
+                registry: Mutex::new(Registry::new()),
+                initialized: Condvar::new(),
+                factories: Mutex::new(Vec::new()),
+                #[cfg(feature = "serde")]
+                serializers: Mutex::new(Vec::new()),
+                parent,
+            });
+            stores.push(store.clone());
+            store
+        });
+
+        IsolatedStateStore {
+            store,
+            _not_send: PhantomData,
+        }
+    }
+
+    fn lock_registry(&self) -> MutexGuard<'_, Registry> {
+        // The registry is never left in an inconsistent state,
+        // hence it is safe to ignore poisoning
+        self.registry.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the state `U` or initializes it using `state`.
+    ///
+    /// The initializer is called without holding the lock of this store,
+    /// which allows it to access other states. If another thread is already
+    /// initializing the same state, this waits for it to finish.
+    /// Returns [`StateError::InitializationCycle`] if waiting would never finish.
+    fn insert_state_if_not_exists<U: 'static + Clone + Send, F: FnOnce() -> U>(
+        &self,
+        name: Option<&str>,
+        state: F,
+    ) -> Result<U, StateError> {
+        let key = StateKey::of::<U>(name);
+        let thread = thread::current().id();
+        let mut registry = self.lock_registry();
+
+        loop {
+            if let Some(state) = registry.states.as_ref().and_then(|s| s.get(&key)) {
+                return state
+                    .downcast_ref::<U>()
+                    .cloned()
+                    .ok_or_else(StateError::type_mismatch::<U>);
+            }
+
+            match registry.initializer_of(&key) {
+                None => break,
+                Some(initializer) if initializer == thread => {
+                    return Err(StateError::initialization_cycle::<U>(name));
+                }
+                Some(initializer) if registry.waits_for(initializer, thread) => {
+                    return Err(StateError::initialization_cycle::<U>(name));
+                }
+                Some(_) => {
+                    registry.waiting.push((thread, key.clone()));
+                    registry = self
+                        .initialized
+                        .wait(registry)
+                        .unwrap_or_else(PoisonError::into_inner);
+                    registry.waiting.retain(|(t, _)| *t != thread);
+                }
+            }
+        }
+
+        if let Some(state) = self.parent.get().and_then(|p| p.find_state::<U>(name).ok()) {
+            return Ok(state);
+        }
+
+        registry.initializing.push((key.clone(), thread));
+        drop(registry);
+
+        let guard = InitializationGuard { store: self, key };
+        let state = state();
+
+        let mut registry = self.lock_registry();
+        let mut inserted = false;
+        // The state may have been initialized using `init` in the meantime
+        let res = registry
+            .states
+            .get_or_insert_with(HashMap::new)
+            .entry(guard.key.clone())
+            .or_insert_with(|| {
+                inserted = true;
+                Box::new(state)
+            })
+            .downcast_ref::<U>()
+            .cloned()
+            .ok_or_else(StateError::type_mismatch::<U>);
+
+        drop(registry);
+        if let (true, Ok(state)) = (inserted, &res) {
+            self.inserted(&guard.key, state);
+        }
+
+        drop(guard);
+        res
+    }
+
+    pub(crate) fn insert_state<U: 'static + Clone + Send>(&self, name: Option<&str>, state: U) {
+        let key = StateKey::of::<U>(name);
+        let mut registry = self.lock_registry();
+        registry
+            .states
+            .get_or_insert_with(HashMap::new)
+            .insert(key.clone(), Box::new(state.clone()));
+        // The hooks of the replaced state must not be called for the new one
+        registry.lifecycle.retain(|(k, _)| *k != key);
+        drop(registry);
+
+        self.inserted(&key, &state);
+    }
+
+    /// Passes the newly inserted `state` to its watcher, if it is being watched.
+    #[cfg(feature = "tokio")]
+    fn inserted<U: 'static>(&self, key: &StateKey, state: &U) {
+        let watcher = self
+            .lock_registry()
+            .watchers
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, watcher)| watcher.clone());
+
+        // The watcher locks the state, hence it must be called without holding the registry
+        if let Some(watcher) = watcher {
+            watcher.attach(state);
+        }
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    fn inserted<U: 'static>(&self, _key: &StateKey, _state: &U) {}
+
+    /// Returns a receiver of the changes of the `MutAppState<T>` in this store
+    /// or in the store it falls back to.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn watch<T>(&self) -> Result<tokio::sync::watch::Receiver<Arc<T>>, StateError>
+    where
+        T: 'static + Clone + Send + Sync,
+    {
+        let state = match self.find_own_state::<MutAppState<T>>(None) {
+            Ok(state) => state,
+            Err(err) => {
+                return match self.parent.get() {
+                    Some(parent) => parent.watch(),
+                    None => Err(err),
+                }
+            }
+        };
+
+        let key = StateKey::of::<MutAppState<T>>(None);
+        let find = |registry: &Registry| {
+            registry
+                .watchers
+                .iter()
+                .find(|(k, _)| *k == key)
+                .and_then(|(_, watcher)| watcher.as_any().downcast_ref::<Watcher<T>>())
+                .map(Watcher::receiver)
+        };
+
+        if let Some(receiver) = find(&self.lock_registry()) {
+            return Ok(receiver);
+        }
+
+        // Creating the watcher locks the state, hence it must be created without holding the registry
+        let watcher = Watcher::new(&state)?;
+        let mut registry = self.lock_registry();
+        if let Some(receiver) = find(&registry) {
+            return Ok(receiver);
+        }
+
+        let receiver = watcher.receiver();
+        registry.watchers.push((key, watcher));
+        Ok(receiver)
+    }
+
+    fn find_state<U: 'static + Clone>(&self, name: Option<&str>) -> Result<U, StateError> {
+        let res = self.find_own_state::<U>(name);
+        match self.parent.get() {
+            Some(parent) if res.is_err() => parent.find_state(name),
+            _ => res,
+        }
+    }
+
+    fn find_own_state<U: 'static + Clone>(&self, name: Option<&str>) -> Result<U, StateError> {
+        let registry = self.lock_registry();
+        Ok(registry
+            .states
+            .as_ref()
+            .ok_or(StateError::StoreNotInitialized)?
+            .get(&StateKey::of::<U>(name))
+            .ok_or_else(|| StateError::not_found::<U>(name))?
+            .downcast_ref::<U>()
+            .ok_or_else(StateError::type_mismatch::<U>)?
+            .clone())
+    }
+
+    /// Initializes the state `U` in this store with the given value.
+    /// If the state has already been initialized, this will overwrite the existing state.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use app_state::{AppState, StateStore};
+    ///
+    /// struct MyState {
+    ///   counter: u32,
+    /// }
+    ///
+    /// fn main() {
+    ///   let store = StateStore::new();
+    ///   store.init::<AppState<_>, _>(MyState { counter: 0 });
+    /// }
+    /// ```
+    pub fn init<U, T>(&self, state: T)
+    where
+        T: 'static + Send,
+        U: 'static + CreateAppState<T> + Clone + Send,
+    {
+        #[cfg(feature = "log")]
+        log::debug!("Initializing state {}", std::any::type_name::<T>());
+
+        self.insert_state(None, U::new(state));
+    }
+
+    /// Initializes the state `U` with the given name in this store.
+    /// Named states are independent of each other and of the unnamed state,
+    /// even if they are of the same type.
+    /// If the state has already been initialized, this will overwrite the existing state.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use app_state::{AppState, StateStore};
+    ///
+    /// struct Pool {
+    ///   url: String,
+    /// }
+    ///
+    /// fn main() {
+    ///   let store = StateStore::new();
+    ///   store.init_named::<AppState<_>, _>("replica", Pool { url: "replica".to_string() });
+    ///
+    ///   let pool = store.get_named::<AppState<Pool>>("replica");
+    ///   assert_eq!(pool.url, "replica");
+    /// }
+    /// ```
+    pub fn init_named<U, T>(&self, name: &str, state: T)
+    where
+        T: 'static + Send,
+        U: 'static + CreateAppState<T> + Clone + Send,
+    {
+        #[cfg(feature = "log")]
+        log::debug!(
+            "Initializing state {} named '{}'",
+            std::any::type_name::<T>(),
+            name
+        );
+
+        let state = U::new(state);
+        state.set_metrics_name(name);
+        self.insert_state(Some(name), state);
+    }
+
+    /// Initializes the state `U` in this store with the given value
+    /// and calls its [`StateLifecycle::on_init`] hook.
+    /// The [`StateLifecycle::on_shutdown`] hook is called by [`StateStore::shutdown`].
+    /// If the state has already been initialized, this will overwrite the existing state.
+    pub fn init_with_lifecycle<U, T>(&self, state: T)
+    where
+        T: 'static + Send,
+        U: 'static + CreateAppState<T> + StateLifecycle + Clone + Send,
+    {
+        #[cfg(feature = "log")]
+        log::debug!(
+            "Initializing state {} with lifecycle hooks",
+            std::any::type_name::<T>()
+        );
+
+        self.insert_with_lifecycle(U::new(state));
+    }
+
+    /// Inserts `state` into this store, calling its `on_init` hook
+    /// and registering its `on_shutdown` hook.
+    pub(crate) fn insert_with_lifecycle<U>(&self, state: U)
+    where
+        U: 'static + StateLifecycle + Clone + Send,
+    {
+        let key = StateKey::of::<U>(None);
+        let mut registry = self.lock_registry();
+        registry
+            .states
+            .get_or_insert_with(HashMap::new)
+            .insert(key.clone(), Box::new(state.clone()));
+        registry.lifecycle.retain(|(k, _)| *k != key);
+        registry.lifecycle.push((
+            key.clone(),
+            Arc::new(|store: &StateStore| {
+                if let Ok(state) = store.find_own_state::<U>(None) {
+                    state.on_shutdown();
+                }
+            }),
+        ));
+        drop(registry);
+
+        self.inserted(&key, &state);
+        state.on_init();
+    }
+
+    /// Initializes the `AppState<T>` in this store with the given `Arc`.
+    /// This allows initializing states of unsized types, for example trait objects.
+    /// If the state has already been initialized, this will overwrite the existing state.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use app_state::{AppState, StateStore};
+    /// use std::fmt::Display;
+    /// use std::sync::Arc;
+    ///
+    /// fn main() {
+    ///   let store = StateStore::new();
+    ///   store.init_arc::<dyn Display + Send + Sync>(Arc::new(42));
+    ///
+    ///   let state = store.get::<AppState<dyn Display + Send + Sync>>();
+    ///   assert_eq!(state.to_string(), "42");
+    /// }
+    /// ```
+    pub fn init_arc<T: ?Sized + 'static + Send + Sync>(&self, state: Arc<T>) {
+        #[cfg(feature = "log")]
+        log::debug!("Initializing state {}", std::any::type_name::<T>());
+
+        self.insert_state(None, AppState::from(state));
+    }
+
+    /// Initializes the `AppState<T>` with the given name in this store with the given `Arc`.
+    /// See [`StateStore::init_arc`] for details.
+    pub fn init_arc_named<T: ?Sized + 'static + Send + Sync>(&self, name: &str, state: Arc<T>) {
+        #[cfg(feature = "log")]
+        log::debug!(
+            "Initializing state {} named '{}'",
+            std::any::type_name::<T>(),
+            name
+        );
+
+        self.insert_state(Some(name), AppState::from(state));
+    }
+
+    /// Initializes the state `U` in this store with the given value.
+    /// If the state has already been initialized, this will do nothing.
+    pub fn init_if_not_exists<U, T, F>(&self, state: F)
+    where
+        T: 'static + Send,
+        U: 'static + CreateAppState<T> + Clone + Send,
+        F: FnOnce() -> T,
+    {
+        self.get_or_insert_with::<U, T, F>(state);
+    }
+
+    /// Removes the state `U` from this store and returns it.
+    /// Returns `None` if the state has not been initialized.
+    ///
+    /// Handles to the state which are still held elsewhere stay valid
+    /// but are no longer connected to this store.
+    pub fn remove<U: 'static + Clone>(&self) -> Option<U> {
+        #[cfg(feature = "log")]
+        log::debug!("Removing state {}", std::any::type_name::<U>());
+
+        self.remove_state(None)
+    }
+
+    /// Removes the state `U` with the given name from this store and returns it.
+    /// Returns `None` if the state has not been initialized.
+    pub fn remove_named<U: 'static + Clone>(&self, name: &str) -> Option<U> {
+        #[cfg(feature = "log")]
+        log::debug!(
+            "Removing state {} named '{}'",
+            std::any::type_name::<U>(),
+            name
+        );
+
+        self.remove_state(Some(name))
+    }
+
+    fn remove_state<U: 'static + Clone>(&self, name: Option<&str>) -> Option<U> {
+        let key = StateKey::of::<U>(name);
+        let mut registry = self.lock_registry();
+        registry.lifecycle.retain(|(k, _)| *k != key);
+
+        // Closes the receivers of the watcher, like `clear` does
+        #[cfg(feature = "tokio")]
+        let watchers = registry
+            .watchers
+            .iter()
+            .position(|(k, _)| *k == key)
+            .map(|i| registry.watchers.swap_remove(i));
+
+        let state = registry
+            .states
+            .as_mut()
+            .and_then(|states| states.remove(&key));
+        drop(registry);
+
+        #[cfg(feature = "tokio")]
+        drop(watchers);
+
+        state
+            .and_then(|state| state.downcast::<U>().ok())
+            .map(|state| *state)
+    }
+
+    /// Registers a factory in this store.
+    /// The factory is built by the next call to [`StateStore::initialize_all`].
+    /// See [`StateFactory`] for details.
+    pub fn register_factory(&self, factory: StateFactory) {
+        #[cfg(feature = "log")]
+        log::debug!("Registering factory for state {}", factory.type_name());
+
+        self.factories
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Arc::new(factory));
+    }
+
+    /// Returns the factories of this store and of the stores it falls back to.
+    fn all_factories(&self) -> Vec<Arc<StateFactory>> {
+        let mut factories = self
+            .parent
+            .get()
+            .map(StateStore::all_factories)
+            .unwrap_or_default();
+        factories.extend(
+            self.factories
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .iter()
+                .cloned(),
+        );
+
+        factories
+    }
+
+    /// Initializes all states provided by the factories registered in this store
+    /// and the stores it falls back to.
+    /// See [`initialize_all`](crate::initialize_all) for details.
+    pub fn initialize_all(&self) -> Result<(), StateError> {
+        for factory in initialization_order(self, &self.all_factories())? {
+            #[cfg(feature = "log")]
+            log::debug!("Initializing state {} from factory", factory.type_name());
+
+            factory.init(self);
+        }
+
+        Ok(())
+    }
+
+    /// Registers the state `U` to be included in snapshots of this store.
+    /// See [`register_serializable`](crate::register_serializable) for details.
+    #[cfg(feature = "serde")]
+    pub fn register_serializable<U: SerializableState>(&self) {
+        #[cfg(feature = "log")]
+        log::debug!(
+            "Registering serializable state {}",
+            std::any::type_name::<U>()
+        );
+
+        let serializer = StateSerializer::new::<U>();
+        let inherited = self
+            .parent
+            .get()
+            .map(StateStore::all_serializers)
+            .unwrap_or_default();
+        let mut serializers = self
+            .serializers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Reported by `snapshot` and `restore`, as this may be called before `main`
+        #[cfg(feature = "log")]
+        if inherited
+            .iter()
+            .chain(serializers.iter())
+            .any(|s| s.conflicts_with(&serializer))
+        {
+            log::error!(
+                "The type {} has already been registered as a different kind of serializable state",
+                serializer.type_name()
+            );
+        }
+
+        serializers.retain(|s| !s.same_state(&serializer));
+        serializers.push(Arc::new(serializer));
+    }
+
+    /// Returns the serializers of this store and of the stores it falls back to.
+    #[cfg(feature = "serde")]
+    fn all_serializers(&self) -> Vec<Arc<StateSerializer>> {
+        let mut serializers = self
+            .parent
+            .get()
+            .map(StateStore::all_serializers)
+            .unwrap_or_default();
+        serializers.extend(
+            self.serializers
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .iter()
+                .cloned(),
+        );
+
+        serializers
+    }
+
+    /// Serializes all serializable states of this store
+    /// and the stores it falls back to.
+    /// See [`snapshot`](crate::snapshot) for details.
+    #[cfg(feature = "serde")]
+    pub fn snapshot(&self) -> Result<Snapshot, StateError> {
+        let serializers = self.all_serializers();
+        StateSerializer::check_conflicts(&serializers)?;
+
+        let mut snapshot = Snapshot::default();
+        for serializer in serializers {
+            if let Some(value) = serializer.serialize(self)? {
+                snapshot.insert(serializer.type_name(), value);
+            }
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Initializes all states contained in `snapshot` in this store.
+    /// See [`restore`](crate::restore) for details.
+    #[cfg(feature = "serde")]
+    pub fn restore(&self, snapshot: &Snapshot) -> Result<(), StateError> {
+        let serializers = self.all_serializers();
+        StateSerializer::check_conflicts(&serializers)?;
+        if let Some((type_name, _)) = snapshot
+            .iter()
+            .find(|(name, _)| !serializers.iter().any(|s| s.type_name() == *name))
+        {
+            return Err(StateError::UnknownState {
+                type_name: type_name.to_string(),
+            });
+        }
+
+        let restored = serializers
+            .iter()
+            .filter_map(|s| snapshot.get_by_name(s.type_name()).map(|v| s.restore(v)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        #[cfg(feature = "log")]
+        log::debug!("Restoring {} states from snapshot", restored.len());
+
+        for restore in restored {
+            restore(self);
+        }
+
+        Ok(())
+    }
+
+    /// Removes all states from this store.
+    pub fn clear(&self) {
+        #[cfg(feature = "log")]
+        log::debug!("Removing all states");
+
+        // Drop the states after releasing the lock
+        let mut registry = self.lock_registry();
+        let states = registry.states.take();
+        let lifecycle = std::mem::take(&mut registry.lifecycle);
+        #[cfg(feature = "tokio")]
+        let watchers = std::mem::take(&mut registry.watchers);
+        drop(registry);
+        drop((states, lifecycle));
+
+        // Closes the receivers of the watchers
+        #[cfg(feature = "tokio")]
+        drop(watchers);
+    }
+
+    /// Calls the shutdown hooks of all states in this store
+    /// in reverse order of their initialization and removes all states afterwards.
+    /// See [`shutdown`](crate::shutdown) for details.
+    pub fn shutdown(&self) {
+        #[cfg(feature = "log")]
+        log::debug!("Shutting down all states");
+
+        let hooks = self
+            .lock_registry()
+            .lifecycle
+            .iter()
+            .rev()
+            .map(|(_, hook)| hook.clone())
+            .collect::<Vec<_>>();
+        for hook in hooks {
+            hook(self);
+        }
+
+        self.clear();
+    }
+
+    /// Returns the state `U` from this store.
+    /// If the state has not been initialized, this will panic.
+    pub fn get<U: 'static + Clone>(&self) -> U {
+        match self.find_state::<U>(None) {
+            Ok(state) => state,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Returns the state `U` from this store.
+    /// If the state has not been initialized, this will return `Err`.
+    pub fn try_get<U: 'static + Clone>(&self) -> Result<U, StateError> {
+        self.find_state(None)
+    }
+
+    /// Returns the state `U` with the given name from this store.
+    /// If the state has not been initialized, this will panic.
+    pub fn get_named<U: 'static + Clone>(&self, name: &str) -> U {
+        match self.find_state::<U>(Some(name)) {
+            Ok(state) => state,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Returns the state `U` with the given name from this store.
+    /// If the state has not been initialized, this will return `Err`.
+    pub fn try_get_named<U: 'static + Clone>(&self, name: &str) -> Result<U, StateError> {
+        self.find_state(Some(name))
+    }
+
+    /// Returns the state `U` from this store.
+    /// Inserts the supplied value if the state has not been initialized.
+    pub fn get_or_insert<U, T>(&self, val: T) -> U
+    where
+        T: 'static + Send,
+        U: 'static + CreateAppState<T> + Clone + Send,
+    {
+        self.get_or_insert_with(|| val)
+    }
+
+    /// Returns the state `U` from this store.
+    /// Inserts the result of `f` if the state has not been initialized.
+    ///
+    /// `f` is called without holding the lock of this store, hence it may
+    /// access other states. If `f` (indirectly) requires the state it is
+    /// initializing, this will panic. Use [`StateStore::try_get_or_insert_with`]
+    /// in order to handle this case.
+    pub fn get_or_insert_with<U, T, F>(&self, f: F) -> U
+    where
+        T: 'static + Send,
+        U: 'static + CreateAppState<T> + Clone + Send,
+        F: FnOnce() -> T,
+    {
+        match self.try_get_or_insert_with(f) {
+            Ok(state) => state,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Returns the state `U` from this store.
+    /// Inserts the result of `f` if the state has not been initialized.
+    ///
+    /// If another thread is initializing the same state, this waits until
+    /// the other thread has finished. If `f` (indirectly) requires the state
+    /// it is initializing, this returns [`StateError::InitializationCycle`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// use app_state::{AppState, StateError, StateStore};
+    ///
+    /// struct Config {
+    ///   url: String,
+    /// }
+    ///
+    /// struct Client {
+    ///   url: String,
+    /// }
+    ///
+    /// fn main() {
+    ///   let store = StateStore::new();
+    ///   let client = store.try_get_or_insert_with::<AppState<_>, _, _>(|| {
+    ///     // Initializers may access other states
+    ///     let config = store.get_or_insert_with::<AppState<_>, _, _>(|| Config {
+    ///       url: "localhost".to_string(),
+    ///     });
+    ///
+    ///     Client { url: config.url.clone() }
+    ///   });
+    ///   assert_eq!(client.unwrap().url, "localhost");
+    /// }
+    /// ```
+    pub fn try_get_or_insert_with<U, T, F>(&self, f: F) -> Result<U, StateError>
+    where
+        T: 'static + Send,
+        U: 'static + CreateAppState<T> + Clone + Send,
+        F: FnOnce() -> T,
+    {
+        self.insert_state_if_not_exists(None, || {
+            #[cfg(feature = "log")]
+            log::debug!("Initializing state {}", std::any::type_name::<T>());
+
+            U::new(f())
+        })
+    }
+
+    /// Returns the state `U` with the given name from this store.
+    /// Inserts the result of `f` if the state has not been initialized.
+    /// See [`StateStore::get_or_insert_with`] for details.
+    pub fn get_or_insert_with_named<U, T, F>(&self, name: &str, f: F) -> U
+    where
+        T: 'static + Send,
+        U: 'static + CreateAppState<T> + Clone + Send,
+        F: FnOnce() -> T,
+    {
+        match self.try_get_or_insert_with_named(name, f) {
+            Ok(state) => state,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Returns the state `U` with the given name from this store.
+    /// Inserts the result of `f` if the state has not been initialized.
+    /// See [`StateStore::try_get_or_insert_with`] for details.
+    pub fn try_get_or_insert_with_named<U, T, F>(&self, name: &str, f: F) -> Result<U, StateError>
+    where
+        T: 'static + Send,
+        U: 'static + CreateAppState<T> + Clone + Send,
+        F: FnOnce() -> T,
+    {
+        self.insert_state_if_not_exists(Some(name), || {
+            #[cfg(feature = "log")]
+            log::debug!(
+                "Initializing state {} named '{}'",
+                std::any::type_name::<T>(),
+                name
+            );
+
+            let state = U::new(f());
+            state.set_metrics_name(name);
+            state
+        })
+    }
+
+    /// Returns the state `U` from this store.
+    /// Inserts the default value of `T` if the state has not been initialized.
+    pub fn get_or_insert_default<U, T>(&self) -> U
+    where
+        T: 'static + Send + Default,
+        U: 'static + CreateAppState<T> + Clone + Send,
+    {
+        let res = self.insert_state_if_not_exists(None, || {
+            #[cfg(feature = "log")]
+            log::debug!("Initializing state {}", std::any::type_name::<T>());
+
+            U::new_default()
+        });
+
+        match res {
+            Ok(state) => state,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Returns the state `U` with the given name from this store.
+    /// Inserts the default value of `T` if the state has not been initialized.
+    pub fn get_or_insert_default_named<U, T>(&self, name: &str) -> U
+    where
+        T: 'static + Send + Default,
+        U: 'static + CreateAppState<T> + Clone + Send,
+    {
+        let res = self.insert_state_if_not_exists(Some(name), || {
+            #[cfg(feature = "log")]
+            log::debug!(
+                "Initializing state {} named '{}'",
+                std::any::type_name::<T>(),
+                name
+            );
+
+            let state = U::new_default();
+            state.set_metrics_name(name);
+            state
+        });
+
+        match res {
+            Ok(state) => state,
+            Err(err) => panic!("{}", err),
+        }
+    }
+}
+
+/// Removes all states from the state store.
+///
+/// Handles to removed states which are still held elsewhere stay valid
+/// but are no longer connected to the store: Re-initializing a state
+/// creates a new, independent value.
+///
+/// If the current thread has been isolated using [`StateStore::isolated`],
+/// this only removes the states of the isolated store.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{AppState, AppStateTrait, clear_all};
+///
+/// struct MyState {
+///   counter: u32,
+/// }
+///
+/// fn main() {
+///   AppState::init(MyState { counter: 0 });
+///   clear_all();
+///   assert!(AppState::<MyState>::try_get().is_err());
+/// }
+/// ```
+pub fn clear_all() {
+    with_current_store(|store| store.clear());
+}
+
+impl Default for StateStore {
+    fn default() -> Self {
+        StateStore::new()
+    }
+}
+
+impl Drop for IsolatedStateStore {
+    fn drop(&mut self) {
+        ISOLATED_STORES.with(|stores| {
+            let mut stores = stores.borrow_mut();
+            if let Some(pos) = stores.iter().rposition(|s| Arc::ptr_eq(s, &self.store)) {
+                stores.remove(pos);
+            }
+        });
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError, RwLock, Weak};
+
+type Callback<T> = Arc<dyn Fn(&T) + Send + Sync>;
+
+/// The subscribers of a single state, shared by all of its handles.
+pub(crate) struct Subscribers<T: ?Sized> {
+    next_id: AtomicU64,
+    callbacks: RwLock<Vec<(u64, Callback<T>)>>,
+}
+
+impl<T: ?Sized> Subscribers<T> {
+    pub(crate) fn new() -> Subscribers<T> {
+        Subscribers {
+            next_id: AtomicU64::new(0),
+            callbacks: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn subscribe(self: &Arc<Self>, callback: Callback<T>) -> Subscription<T> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.callbacks
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((id, callback));
+
+        Subscription {
+            subscribers: Arc::downgrade(self),
+            id,
+        }
+    }
+
+    /// Calls all subscribers with the current `value` of the state.
+    pub(crate) fn notify(&self, value: &T) {
+        // Release the lock before calling the subscribers, so they may unsubscribe
+        let callbacks = self
+            .callbacks
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(_, callback)| callback.clone())
+            .collect::<Vec<_>>();
+
+        for callback in callbacks {
+            callback(value);
+        }
+    }
+
+    fn unsubscribe(&self, id: u64) {
+        self.callbacks
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|(other, _)| *other != id);
+    }
+}
+
+/// A handle to a subscription to the changes of a state,
+/// returned by [`MutAppState::subscribe`](crate::MutAppState::subscribe).
+///
+/// Dropping the handle does not cancel the subscription,
+/// use [`Subscription::unsubscribe`] in order to stop receiving changes.
+pub struct Subscription<T: ?Sized> {
+    subscribers: Weak<Subscribers<T>>,
+    id: u64,
+}
+
+impl<T: ?Sized> Subscription<T> {
+    /// Cancels the subscription.
+    pub fn unsubscribe(self) {
+        if let Some(subscribers) = self.subscribers.upgrade() {
+            subscribers.unsubscribe(self.id);
+        }
+    }
+}
+
+impl<T: ?Sized> std::fmt::Debug for Subscription<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Subscription")
+            .field("id", &self.id)
+            .finish()
+    }
+}
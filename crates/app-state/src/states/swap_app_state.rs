@@ -0,0 +1,87 @@
+use crate::states::traits::CreateAppState;
+use crate::AppStateTrait;
+use arc_swap::ArcSwap;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// A read-mostly app state which can be replaced atomically.
+///
+/// Reading the state using `load()` does not lock and returns a cheap
+/// snapshot of the current version. New versions are published
+/// atomically using `store()` or `rcu()`, readers holding an older
+/// snapshot keep seeing that version until they load the state again.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{SwapAppState, AppStateTrait, stateful};
+///
+/// struct Config {
+///   verbose: bool,
+/// }
+///
+/// #[stateful]
+/// fn func(config: SwapAppState<Config>) {
+///   if config.load().verbose {
+///     println!("Verbose output enabled");
+///   }
+///
+///   config.store(Config { verbose: false });
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SwapAppState<T>(Arc<ArcSwap<T>>);
+
+impl<T: 'static + Send + Sync> SwapAppState<T> {
+    /// Returns a snapshot of the current version of the state.
+    pub fn load(&self) -> Arc<T> {
+        self.0.load_full()
+    }
+
+    /// Atomically replaces the state with the given value.
+    pub fn store(&self, state: T) {
+        self.0.store(Arc::new(state));
+    }
+
+    /// Atomically replaces the state with the value returned by `f`
+    /// and returns the previous version of the state.
+    /// `f` receives the current version and may be called multiple times
+    /// if the state is replaced concurrently.
+    pub fn rcu<F: FnMut(&T) -> T>(&self, mut f: F) -> Arc<T> {
+        self.0.rcu(|old| f(old))
+    }
+}
+
+impl<T: 'static + Send + Sync> CreateAppState<T> for SwapAppState<T> {
+    fn new(state: T) -> SwapAppState<T> {
+        SwapAppState(Arc::new(ArcSwap::from_pointee(state)))
+    }
+}
+
+impl<T: 'static + Send + Sync> AppStateTrait<T, SwapAppState<T>> for SwapAppState<T> {}
+
+impl<T> SwapAppState<T> {
+    /// Unwraps to the internal `Arc<ArcSwap<T>>`
+    pub fn into_inner(self) -> Arc<ArcSwap<T>> {
+        self.0
+    }
+}
+
+impl<T> Deref for SwapAppState<T> {
+    type Target = Arc<ArcSwap<T>>;
+
+    fn deref(&self) -> &Arc<ArcSwap<T>> {
+        &self.0
+    }
+}
+
+impl<T> Clone for SwapAppState<T> {
+    fn clone(&self) -> SwapAppState<T> {
+        SwapAppState(self.0.clone())
+    }
+}
+
+impl<T> From<Arc<ArcSwap<T>>> for SwapAppState<T> {
+    fn from(arc: Arc<ArcSwap<T>>) -> Self {
+        SwapAppState(arc)
+    }
+}
@@ -1,5 +1,6 @@
-use crate::states::{find_state, find_state_unwrap, insert_state, insert_state_if_not_exists};
-use std::error::Error;
+use crate::states::metrics;
+use crate::states::store::with_current_store;
+use crate::{StateError, StateLifecycle};
 
 pub trait InitAppState {
     fn init_app_state(self);
@@ -9,13 +10,31 @@ pub trait InitMutAppState {
     fn init_mut_app_state(self);
 }
 
-pub trait CreateAppState<T: 'static + Send> {
-    fn new(state: T) -> Self;
+pub trait InitRwAppState {
+    fn init_rw_app_state(self);
+}
+
+pub trait CreateAppState<T: ?Sized + 'static + Send> {
+    fn new(state: T) -> Self
+    where
+        T: Sized;
+
+    fn new_default() -> Self
+    where
+        Self: Sized,
+        T: Sized + Default,
+    {
+        Self::new(T::default())
+    }
+
+    /// Called before the state is inserted into a state store under `name`.
+    /// Used to record the metrics of locks of the state under its name.
+    fn set_metrics_name(&self, _name: &str) {}
 }
 
 pub trait AppStateTrait<T, U>
 where
-    T: 'static + Send,
+    T: ?Sized + 'static + Send,
     U: 'static + AppStateTrait<T, U> + CreateAppState<T> + Clone + Send,
 {
     /// Initializes the state store with the given state.
@@ -34,11 +53,23 @@ where
     ///   AppState::init(state);
     /// }
     /// ```
-    fn init(state: T) {
-        #[cfg(feature = "log")]
-        log::debug!("Initializing state {}", std::any::type_name::<T>());
+    fn init(state: T)
+    where
+        T: Sized,
+    {
+        with_current_store(|store| store.init::<U, T>(state));
+    }
 
-        insert_state(U::new(state));
+    /// Initializes the state store with the given state and calls its
+    /// [`StateLifecycle::on_init`] hook. The [`StateLifecycle::on_shutdown`]
+    /// hook is called by [`shutdown`](crate::shutdown).
+    /// If the state store has already been initialized, this will overwrite the existing state.
+    fn init_with_lifecycle(state: T)
+    where
+        T: Sized,
+        U: StateLifecycle,
+    {
+        with_current_store(|store| store.init_with_lifecycle::<U, T>(state));
     }
 
     /// Initializes the state store with the given state.
@@ -58,60 +89,197 @@ where
     ///   AppState::init_if_not_exists(|| MyState { counter: 0 });
     /// }
     /// ```
-    fn init_if_not_exists<F: FnOnce() -> T>(state: F) {
-        insert_state_if_not_exists(|| {
-            #[cfg(feature = "log")]
-            log::debug!("Initializing state {}", std::any::type_name::<T>());
-
-            U::new(state())
-        });
+    fn init_if_not_exists<F: FnOnce() -> T>(state: F)
+    where
+        T: Sized,
+    {
+        with_current_store(|store| store.init_if_not_exists::<U, T, F>(state));
     }
 
     /// Returns a reference to the state.
     /// If the state store has not been initialized, this will panic.
     fn get() -> U {
-        find_state_unwrap()
+        metrics::record_get::<U, T>(None);
+        with_current_store(|store| store.get())
     }
 
     /// Returns a reference to the state.
     /// If the state store has not been initialized, this will return `Err`.
-    fn try_get() -> Result<U, Box<dyn Error>> {
-        find_state()
+    fn try_get() -> Result<U, StateError> {
+        metrics::record_get::<U, T>(None);
+        with_current_store(|store| store.try_get())
+    }
+
+    /// Initializes the state with the given name.
+    /// Named states are independent of each other and of the unnamed state,
+    /// even if they are of the same type.
+    /// If the state has already been initialized, this will overwrite the existing state.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use app_state::{AppState, AppStateTrait};
+    ///
+    /// struct Pool {
+    ///   url: String,
+    /// }
+    ///
+    /// fn main() {
+    ///   AppState::init_named("primary", Pool { url: "primary".to_string() });
+    ///   AppState::init_named("replica", Pool { url: "replica".to_string() });
+    ///
+    ///   assert_eq!(AppState::<Pool>::get_named("replica").url, "replica");
+    /// }
+    /// ```
+    fn init_named(name: &str, state: T)
+    where
+        T: Sized,
+    {
+        with_current_store(|store| store.init_named::<U, T>(name, state));
+    }
+
+    /// Returns a reference to the state with the given name.
+    /// If the state has not been initialized, this will panic.
+    fn get_named(name: &str) -> U {
+        metrics::record_get::<U, T>(Some(name));
+        with_current_store(|store| store.get_named(name))
+    }
+
+    /// Returns a reference to the state with the given name.
+    /// If the state has not been initialized, this will return `Err`.
+    fn try_get_named(name: &str) -> Result<U, StateError> {
+        metrics::record_get::<U, T>(Some(name));
+        with_current_store(|store| store.try_get_named(name))
+    }
+
+    /// Returns a reference to the state with the given name.
+    /// Inserts the result of `f` if the state has not been initialized.
+    fn get_or_insert_with_named<F: FnOnce() -> T>(name: &str, f: F) -> U
+    where
+        T: Sized,
+    {
+        metrics::record_get::<U, T>(Some(name));
+        with_current_store(|store| store.get_or_insert_with_named(name, f))
+    }
+
+    /// Returns a reference to the state with the given name.
+    /// Inserts the result of `f` if the state has not been initialized.
+    /// If `f` (indirectly) requires the state it is initializing,
+    /// this will return [`StateError::InitializationCycle`].
+    fn try_get_or_insert_with_named<F: FnOnce() -> T>(name: &str, f: F) -> Result<U, StateError>
+    where
+        T: Sized,
+    {
+        metrics::record_get::<U, T>(Some(name));
+        with_current_store(|store| store.try_get_or_insert_with_named(name, f))
+    }
+
+    /// Returns a reference to the state with the given name.
+    /// Inserts the default value of `T` if the state has not been initialized.
+    fn get_or_insert_default_named(name: &str) -> U
+    where
+        T: Sized + Default,
+    {
+        metrics::record_get::<U, T>(Some(name));
+        with_current_store(|store| store.get_or_insert_default_named(name))
+    }
+
+    /// Removes the state with the given name from the state store and returns it.
+    /// Returns `None` if the state has not been initialized.
+    fn remove_named(name: &str) -> Option<U> {
+        with_current_store(|store| store.remove_named(name))
+    }
+
+    /// Removes the state from the state store and returns it.
+    /// Returns `None` if the state has not been initialized.
+    /// Handles to the state which are still held elsewhere stay valid,
+    /// but are no longer connected to the state store.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use app_state::{AppState, AppStateTrait};
+    ///
+    /// struct MyState {
+    ///   counter: u32,
+    /// }
+    ///
+    /// fn main() {
+    ///   AppState::init(MyState { counter: 0 });
+    ///   let state = AppState::<MyState>::remove();
+    ///
+    ///   assert_eq!(state.unwrap().counter, 0);
+    ///   assert!(AppState::<MyState>::try_get().is_err());
+    /// }
+    /// ```
+    fn remove() -> Option<U> {
+        with_current_store(|store| store.remove())
     }
 
     /// Returns a reference to the state.
     /// Inserts the supplied value if the state store has not been initialized.
-    fn get_or_insert(val: T) -> U {
-        insert_state_if_not_exists(|| {
-            #[cfg(feature = "log")]
-            log::debug!("Initializing state {}", std::any::type_name::<T>());
-
-            U::new(val)
-        })
+    fn get_or_insert(val: T) -> U
+    where
+        T: Sized,
+    {
+        metrics::record_get::<U, T>(None);
+        with_current_store(|store| store.get_or_insert(val))
     }
 
     /// Returns a reference to the state.
     /// Inserts the supplied value if the state store has not been initialized.
-    fn get_or_insert_with<F: FnOnce() -> T>(f: F) -> U {
-        insert_state_if_not_exists(|| {
-            #[cfg(feature = "log")]
-            log::debug!("Initializing state {}", std::any::type_name::<T>());
+    ///
+    /// The initializer may access other states. If it (indirectly) requires
+    /// the state it is initializing, this will panic.
+    fn get_or_insert_with<F: FnOnce() -> T>(f: F) -> U
+    where
+        T: Sized,
+    {
+        metrics::record_get::<U, T>(None);
+        with_current_store(|store| store.get_or_insert_with(f))
+    }
 
-            U::new(f())
-        })
+    /// Returns a reference to the state.
+    /// Inserts the result of `f` if the state has not been initialized.
+    /// If `f` (indirectly) requires the state it is initializing,
+    /// this will return [`StateError::InitializationCycle`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// use app_state::{AppState, AppStateTrait};
+    ///
+    /// struct Config {
+    ///   url: String,
+    /// }
+    ///
+    /// struct Client {
+    ///   url: String,
+    /// }
+    ///
+    /// fn main() {
+    ///   let client = AppState::try_get_or_insert_with(|| {
+    ///     let config = AppState::get_or_insert_with(|| Config {
+    ///       url: "localhost".to_string(),
+    ///     });
+    ///     Client { url: config.url.clone() }
+    ///   });
+    ///
+    ///   assert_eq!(client.unwrap().url, "localhost");
+    /// }
+    /// ```
+    fn try_get_or_insert_with<F: FnOnce() -> T>(f: F) -> Result<U, StateError>
+    where
+        T: Sized,
+    {
+        metrics::record_get::<U, T>(None);
+        with_current_store(|store| store.try_get_or_insert_with(f))
     }
 
     /// Returns a reference to the state.
     /// Inserts the default value of `T` if the state store has not been initialized.
     fn get_or_insert_default() -> U
     where
-        T: Default,
+        T: Sized + Default,
     {
-        insert_state_if_not_exists(|| {
-            #[cfg(feature = "log")]
-            log::debug!("Initializing state {}", std::any::type_name::<T>());
-
-            U::new(T::default())
-        })
+        metrics::record_get::<U, T>(None);
+        with_current_store(|store| store.get_or_insert_default())
     }
 }
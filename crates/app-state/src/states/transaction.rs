@@ -0,0 +1,247 @@
+use crate::states::store::with_current_store;
+use crate::{MutAppState, MutAppStateLock, StateError};
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+
+/// The number of times [`transaction`] runs its closure
+/// before it is aborted due to conflicting changes.
+pub const MAX_TRANSACTION_ATTEMPTS: usize = 100;
+
+/// A state used in a [`Transaction`], holding the working copy of its value.
+struct Entry<T: 'static + Send> {
+    state: MutAppState<T>,
+    /// The version of the state the working copy has been cloned from.
+    version: u64,
+    /// `None` while the working copy is borrowed by a [`TransactionLock`].
+    value: RefCell<Option<T>>,
+    modified: Cell<bool>,
+}
+
+trait TransactionEntry {
+    fn id(&self) -> usize;
+
+    fn into_any(self: Rc<Self>) -> Rc<dyn Any>;
+
+    /// Locks the state and calls `rest` in order to lock the remaining states.
+    /// Writes the working copy back if the state has neither been changed nor
+    /// replaced since it has been cloned and `rest` succeeded.
+    /// Returns whether the transaction succeeded.
+    fn commit(&self, rest: &mut dyn FnMut() -> bool) -> bool;
+}
+
+impl<T: 'static + Send> TransactionEntry for Entry<T> {
+    fn id(&self) -> usize {
+        self.state.id()
+    }
+
+    fn into_any(self: Rc<Self>) -> Rc<dyn Any> {
+        self
+    }
+
+    fn commit(&self, rest: &mut dyn FnMut() -> bool) -> bool {
+        let mut lock = MutAppStateLock::new(&self.state);
+        // The state may have been replaced, e.g. using `init`, since it has been cloned
+        let replaced = with_current_store(|store| store.try_get::<MutAppState<T>>())
+            .map_or(true, |current| current.id() != self.state.id());
+        if replaced || self.state.version() != self.version || !rest() {
+            return false;
+        }
+
+        if self.modified.get() {
+            if let Some(value) = self.value.borrow_mut().take() {
+                *lock = value;
+            }
+        }
+
+        true
+    }
+}
+
+/// Commits `entries`, which must be sorted by the ids of their states.
+fn commit(entries: &[Rc<dyn TransactionEntry>]) -> bool {
+    match entries.split_first() {
+        Some((first, rest)) => first.commit(&mut || commit(rest)),
+        None => true,
+    }
+}
+
+/// A transaction over several mutable states, see [`transaction`].
+pub struct Transaction {
+    entries: RefCell<Vec<Rc<dyn TransactionEntry>>>,
+}
+
+impl Transaction {
+    /// Returns the working copy of the `MutAppState<T>`.
+    /// The state is cloned when it is first used in the transaction,
+    /// locking it again returns the same working copy.
+    /// If the state has not been initialized or is currently locked
+    /// by the same transaction, this will panic.
+    #[track_caller]
+    pub fn lock<T: 'static + Clone + Send>(&self) -> TransactionLock<'_, T> {
+        match self.try_lock() {
+            Ok(lock) => lock,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Returns the working copy of the `MutAppState<T>`.
+    /// If the state has not been initialized, this will return `Err`.
+    /// If the state is currently locked by the same transaction, this will return
+    /// [`StateError::WouldBlock`], as waiting would never finish.
+    #[track_caller]
+    pub fn try_lock<T: 'static + Clone + Send>(
+        &self,
+    ) -> Result<TransactionLock<'_, T>, StateError> {
+        let state = with_current_store(|store| store.try_get::<MutAppState<T>>())?;
+        let existing = self
+            .entries
+            .borrow()
+            .iter()
+            .find(|entry| entry.id() == state.id())
+            .cloned();
+
+        let entry = match existing {
+            Some(entry) => entry
+                .into_any()
+                .downcast::<Entry<T>>()
+                .map_err(|_| StateError::type_mismatch::<MutAppState<T>>())?,
+            None => {
+                let lock = MutAppStateLock::lock_checked(&state)?;
+                let entry = Rc::new(Entry {
+                    version: state.version(),
+                    value: RefCell::new(Some(T::clone(&lock))),
+                    modified: Cell::new(false),
+                    state: state.clone(),
+                });
+                drop(lock);
+
+                self.entries.borrow_mut().push(entry.clone());
+                entry
+            }
+        };
+
+        let value = entry.value.borrow_mut().take();
+        match value {
+            Some(value) => Ok(TransactionLock {
+                entry,
+                value: Some(value),
+                _transaction: PhantomData,
+            }),
+            None => Err(StateError::would_block::<T>()),
+        }
+    }
+}
+
+/// The working copy of a state in a [`Transaction`].
+/// The copy is returned to the transaction when this is dropped.
+pub struct TransactionLock<'a, T: 'static + Send> {
+    entry: Rc<Entry<T>>,
+    /// Only `None` while this is being dropped.
+    value: Option<T>,
+    _transaction: PhantomData<&'a Transaction>,
+}
+
+impl<T: 'static + Send> Deref for TransactionLock<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_ref().unwrap()
+    }
+}
+
+impl<T: 'static + Send> DerefMut for TransactionLock<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.entry.modified.set(true);
+        self.value.as_mut().unwrap()
+    }
+}
+
+impl<T: 'static + Send> Drop for TransactionLock<'_, T> {
+    fn drop(&mut self) {
+        *self.entry.value.borrow_mut() = self.value.take();
+    }
+}
+
+/// Runs `f` on working copies of mutable states and writes all changed copies
+/// back to their states if `f` returns `Ok`, or none of them otherwise.
+/// **`f` may be called more than once**, hence it should not have any side effects
+/// besides changing the working copies.
+///
+/// The states are accessed using [`Transaction::lock`], which clones the value of a
+/// state when it is first used. The states are not locked while `f` is running,
+/// as the states used by `f` are only known once it has returned. Once `f` returns `Ok`,
+/// all used states are locked in the canonical order also used by
+/// [`MutAppState::lock_many`]. If none of them has been changed or replaced since it
+/// has been cloned, the changed copies are written back. Otherwise, the copies are
+/// discarded and `f` is called again with fresh copies. If the states have been changed
+/// during [`MAX_TRANSACTION_ATTEMPTS`] attempts, the transaction is aborted and
+/// [`StateError::TransactionConflict`] is returned, converted into `E`.
+///
+/// If `f` returns `Err` or panics, no state is changed.
+///
+/// # Examples
+/// ```rust
+/// use app_state::{transaction, MutAppState, AppStateTrait};
+/// use std::error::Error;
+///
+/// #[derive(Clone, Default)]
+/// struct Account {
+///   balance: u32,
+/// }
+///
+/// #[derive(Clone, Default)]
+/// struct Ledger {
+///   entries: Vec<u32>,
+/// }
+///
+/// fn main() {
+///   MutAppState::init(Account { balance: 10 });
+///   MutAppState::init(Ledger::default());
+///
+///   let res = transaction(|tx| -> Result<(), Box<dyn Error>> {
+///     let mut account = tx.lock::<Account>();
+///     let mut ledger = tx.lock::<Ledger>();
+///     ledger.entries.push(20);
+///     account.balance = account.balance.checked_sub(20).ok_or("Insufficient funds")?;
+///     Ok(())
+///   });
+///
+///   assert_eq!(res.unwrap_err().to_string(), "Insufficient funds");
+///   assert!(MutAppState::<Ledger>::get().get_mut().entries.is_empty());
+/// }
+/// ```
+pub fn transaction<R, E, F>(mut f: F) -> Result<R, E>
+where
+    F: FnMut(&Transaction) -> Result<R, E>,
+    E: From<StateError>,
+{
+    for _ in 0..MAX_TRANSACTION_ATTEMPTS {
+        let tx = Transaction {
+            entries: RefCell::new(Vec::new()),
+        };
+        let res = f(&tx)?;
+
+        let mut entries = tx.entries.into_inner();
+        entries.sort_by_key(|entry| entry.id());
+        if commit(&entries) {
+            return Ok(res);
+        }
+
+        #[cfg(feature = "log")]
+        log::debug!("Retrying transaction after a conflicting change");
+    }
+
+    #[cfg(feature = "log")]
+    log::error!(
+        "Aborting transaction after {} conflicting attempts",
+        MAX_TRANSACTION_ATTEMPTS
+    );
+
+    Err(StateError::TransactionConflict {
+        attempts: MAX_TRANSACTION_ATTEMPTS,
+    }
+    .into())
+}
@@ -0,0 +1,149 @@
+use crate::states::store::with_current_store;
+use crate::{MutAppState, MutAppStateLock, StateError, Subscription};
+use std::any::Any;
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use tokio::sync::watch;
+
+/// A watcher of a state, stored in the [`StateStore`](crate::StateStore)
+/// in order to follow the state when it is re-initialized.
+pub(crate) trait StateWatcher: Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+
+    /// Publishes the value of the newly initialized `state`
+    /// and follows its changes from now on.
+    fn attach(&self, state: &dyn Any);
+}
+
+/// Publishes snapshots of a `MutAppState<T>` to a watch channel.
+pub(crate) struct Watcher<T: 'static + Send> {
+    this: Weak<Watcher<T>>,
+    sender: watch::Sender<Arc<T>>,
+    subscription: Mutex<Option<Subscription<T>>>,
+}
+
+impl<T: 'static + Clone + Send + Sync> Watcher<T> {
+    pub(crate) fn new(state: &MutAppState<T>) -> Result<Arc<Watcher<T>>, StateError> {
+        // Subscribe while holding the lock, so that no change is missed
+        let lock = MutAppStateLock::lock_checked(state)?;
+        let watcher = Arc::new_cyclic(|this| Watcher {
+            this: this.clone(),
+            sender: watch::Sender::new(Arc::new(T::clone(&lock))),
+            subscription: Mutex::new(None),
+        });
+        watcher.subscribe(state);
+        drop(lock);
+
+        Ok(watcher)
+    }
+
+    pub(crate) fn receiver(&self) -> watch::Receiver<Arc<T>> {
+        self.sender.subscribe()
+    }
+
+    fn subscribe(&self, state: &MutAppState<T>) {
+        let this = self.this.clone();
+        let subscription = state.subscribe(move |value: &T| {
+            if let Some(watcher) = this.upgrade() {
+                watcher.sender.send_replace(Arc::new(value.clone()));
+            }
+        });
+
+        let previous = self
+            .subscription
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .replace(subscription);
+        if let Some(previous) = previous {
+            previous.unsubscribe();
+        }
+    }
+}
+
+impl<T: 'static + Clone + Send + Sync> StateWatcher for Watcher<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn attach(&self, state: &dyn Any) {
+        let Some(state) = state.downcast_ref::<MutAppState<T>>() else {
+            return;
+        };
+
+        match MutAppStateLock::lock_checked(state) {
+            Ok(lock) => {
+                self.sender.send_replace(Arc::new(T::clone(&lock)));
+                self.subscribe(state);
+            }
+            Err(_err) => {
+                #[cfg(feature = "log")]
+                log::error!("Could not watch re-initialized state: {}", _err);
+            }
+        }
+    }
+}
+
+impl<T: 'static + Send> Drop for Watcher<T> {
+    fn drop(&mut self) {
+        let subscription = self
+            .subscription
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(subscription) = subscription {
+            subscription.unsubscribe();
+        }
+    }
+}
+
+impl<T: 'static + Clone + Send + Sync> MutAppState<T> {
+    /// Returns a receiver which is updated with a snapshot of the state
+    /// whenever it is changed or re-initialized, e.g. using `init`.
+    /// Requires the `tokio` feature.
+    ///
+    /// Changes are detected like in [`MutAppState::subscribe`], intermediate
+    /// versions may be skipped if the state changes faster than the receiver is polled.
+    /// The receiver is closed once the state is removed, e.g. using `take`,
+    /// or the state store is cleared or shut down.
+    /// If the state has not been initialized, this will panic.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use app_state::{MutAppState, AppStateTrait};
+    ///
+    /// #[derive(Clone, Default)]
+    /// struct Config {
+    ///   verbose: bool,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///   MutAppState::init(Config::default());
+    ///   let mut config = MutAppState::<Config>::watch();
+    ///
+    ///   tokio::spawn(async {
+    ///     MutAppState::<Config>::get().get_mut().verbose = true;
+    ///   });
+    ///
+    ///   while config.changed().await.is_ok() {
+    ///     if config.borrow_and_update().verbose {
+    ///       break;
+    ///     }
+    ///   }
+    /// }
+    /// ```
+    #[track_caller]
+    pub fn watch() -> watch::Receiver<Arc<T>> {
+        match Self::try_watch() {
+            Ok(receiver) => receiver,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Returns a receiver which is updated with a snapshot of the state
+    /// whenever it is changed or re-initialized.
+    /// If the state has not been initialized, this will return `Err`.
+    /// See [`MutAppState::watch`] for details.
+    pub fn try_watch() -> Result<watch::Receiver<Arc<T>>, StateError> {
+        with_current_store(|store| store.watch::<T>())
+    }
+}
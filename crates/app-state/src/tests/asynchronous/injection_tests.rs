@@ -0,0 +1,65 @@
+use crate::tests::util::StateTrait;
+use crate::{
+    create_creatable_state, create_state, stateful, AppStateTrait, AsyncMutAppState,
+    AsyncMutAppStateLock, AsyncRwAppState, AsyncRwAppStateReadLock, AsyncRwAppStateWriteLock,
+};
+
+#[stateful]
+async fn check_async_mut_state<T: StateTrait>(state: AsyncMutAppState<T>) {
+    assert_eq!(state.lock().await.get_name(), "Hello");
+}
+
+#[stateful]
+async fn change_name_with_lock<T: StateTrait>(mut state: AsyncMutAppStateLock<T>) {
+    tokio::task::yield_now().await;
+    state.set_name("Changed");
+}
+
+#[stateful]
+async fn check_changed_with_lock<T: StateTrait>(state: AsyncMutAppStateLock<T>) {
+    assert_eq!(state.get_name(), "Changed");
+}
+
+#[stateful]
+async fn check_async_rw_state<T: StateTrait>(state: AsyncRwAppState<T>) {
+    assert_eq!(state.read().await.get_name(), "Hello");
+}
+
+#[stateful(init(state))]
+async fn init_and_change_rw_state<T: StateTrait + Default>(mut state: AsyncRwAppStateWriteLock<T>) {
+    assert_eq!(state.get_name(), "Hello");
+    state.set_name("Changed");
+}
+
+#[stateful]
+async fn check_changed_rw_state<T: StateTrait>(state: AsyncRwAppStateReadLock<T>) {
+    assert_eq!(state.get_name(), "Changed");
+}
+
+#[tokio::test]
+async fn test_inject_async_mut_state() {
+    create_state!(AsyncMutAppState);
+    check_async_mut_state::<State>().await;
+}
+
+#[tokio::test]
+async fn test_inject_async_mut_state_lock() {
+    create_state!(AsyncMutAppState);
+    tokio::spawn(change_name_with_lock::<State>())
+        .await
+        .unwrap();
+    check_changed_with_lock::<State>().await;
+}
+
+#[tokio::test]
+async fn test_inject_async_rw_state() {
+    create_state!(AsyncRwAppState);
+    check_async_rw_state::<State>().await;
+}
+
+#[tokio::test]
+async fn test_init_and_change_async_rw_state() {
+    create_creatable_state!();
+    init_and_change_rw_state::<State>().await;
+    check_changed_rw_state::<State>().await;
+}
@@ -0,0 +1,57 @@
+use crate::tests::util::StateTrait;
+use crate::{
+    create_creatable_state, create_state, AppStateTrait, AsyncMutAppState, AsyncRwAppState,
+};
+
+struct NonExistentState {}
+
+#[tokio::test]
+async fn test_get_async_mut_state() {
+    create_state!(AsyncMutAppState);
+    let state = AsyncMutAppState::<State>::get();
+    assert_eq!(state.lock().await.name, "Hello".to_string());
+}
+
+#[tokio::test]
+async fn test_try_get_non_existent_async_mut_state() {
+    create_state!(AsyncMutAppState);
+    assert!(AsyncMutAppState::<NonExistentState>::try_get().is_err());
+}
+
+#[tokio::test]
+async fn test_lock_across_await() {
+    create_state!(AsyncMutAppState);
+
+    let state = AsyncMutAppState::<State>::get();
+    let handle = tokio::spawn(async move {
+        let mut lock = state.lock().await;
+        tokio::task::yield_now().await;
+        lock.name = "Changed".to_string();
+    });
+    handle.await.unwrap();
+
+    let state = AsyncMutAppState::<State>::get();
+    assert_eq!(state.lock().await.name, "Changed".to_string());
+}
+
+#[tokio::test]
+async fn test_get_async_rw_state() {
+    create_state!(AsyncRwAppState);
+
+    let state = AsyncRwAppState::<State>::get();
+    let first = state.read().await;
+    let second = state.read().await;
+    assert_eq!(first.name, second.name);
+    drop((first, second));
+
+    state.write().await.name = "Changed".to_string();
+    assert_eq!(state.read().await.name, "Changed".to_string());
+}
+
+#[tokio::test]
+async fn test_get_or_insert_default() {
+    create_creatable_state!();
+
+    let state = AsyncRwAppState::<State>::get_or_insert_default();
+    assert_eq!(state.read().await.name, "Hello".to_string());
+}
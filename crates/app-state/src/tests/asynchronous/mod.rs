@@ -0,0 +1,3 @@
+mod injection_tests;
+mod manual_tests;
+mod watch_tests;
@@ -0,0 +1,113 @@
+use crate::{shutdown, AppStateTrait, MutAppState, StateError, StateStore};
+use std::future::Future;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Config {
+    verbose: bool,
+    level: u32,
+}
+
+/// Runs `future` on the current thread, so that it uses the isolated store of the test.
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+#[crate::test]
+fn test_watch_changes() {
+    MutAppState::init(Config::default());
+    let mut config = MutAppState::<Config>::watch();
+    assert_eq!(config.borrow_and_update().level, 0);
+
+    block_on(async {
+        MutAppState::<Config>::get().get_mut().level = 1;
+        config.changed().await.unwrap();
+        assert_eq!(config.borrow_and_update().level, 1);
+
+        // Locks which do not mutate the state are not published
+        assert_eq!(MutAppState::<Config>::get().get_mut().level, 1);
+        assert!(!config.has_changed().unwrap());
+    });
+}
+
+#[crate::test]
+fn test_watch_re_initialization() {
+    MutAppState::init(Config::default());
+    let old = MutAppState::<Config>::get();
+    let mut config = MutAppState::<Config>::watch();
+
+    block_on(async {
+        MutAppState::init(Config {
+            verbose: true,
+            level: 2,
+        });
+        config.changed().await.unwrap();
+        assert!(config.borrow_and_update().verbose);
+
+        // The re-initialized state is followed, the replaced one is not
+        MutAppState::<Config>::get().get_mut().level = 3;
+        config.changed().await.unwrap();
+        assert_eq!(config.borrow_and_update().level, 3);
+
+        old.get_mut().level = 4;
+        assert!(!config.has_changed().unwrap());
+    });
+}
+
+#[crate::test]
+fn test_multiple_receivers() {
+    MutAppState::init(Config::default());
+    let first = MutAppState::<Config>::watch();
+    let second = MutAppState::<Config>::watch();
+
+    MutAppState::<Config>::get().get_mut().level = 5;
+    assert_eq!(first.borrow().level, 5);
+    assert_eq!(second.borrow().level, 5);
+    assert!(first.same_channel(&second));
+}
+
+#[crate::test]
+fn test_closed_on_shutdown() {
+    MutAppState::init(Config::default());
+    let mut config = MutAppState::<Config>::watch();
+    shutdown();
+
+    block_on(async {
+        assert!(config.changed().await.is_err());
+    });
+}
+
+#[crate::test]
+fn test_closed_on_remove() {
+    MutAppState::init(Config::default());
+    let mut config = MutAppState::<Config>::watch();
+    assert!(MutAppState::<Config>::remove().is_some());
+
+    block_on(async {
+        assert!(config.changed().await.is_err());
+    });
+}
+
+#[crate::test]
+fn test_closed_on_take() {
+    MutAppState::init(Config::default());
+    let mut config = MutAppState::<Config>::watch();
+    assert!(MutAppState::<Config>::take().is_some());
+
+    block_on(async {
+        assert!(config.changed().await.is_err());
+    });
+}
+
+#[crate::test]
+fn test_watch_uninitialized() {
+    let store = StateStore::new();
+    assert!(matches!(
+        store.watch::<Config>(),
+        Err(StateError::StoreNotInitialized)
+    ));
+    assert!(MutAppState::<Config>::try_watch().is_err());
+}
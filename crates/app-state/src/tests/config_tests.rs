@@ -0,0 +1,229 @@
+use crate::{
+    shutdown, AppStateTrait, ConfigEvent, ConfigSource, ConfigState, JsonFormat, StateError,
+    SwapAppState,
+};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Settings {
+    port: u16,
+    #[serde(default)]
+    database: Database,
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize)]
+struct Database {
+    url: String,
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("app-state-{}-{}", std::process::id(), name))
+}
+
+/// Replaces the file atomically, like most editors do.
+fn replace(path: &Path, contents: &str) {
+    let mut file_name = path.file_name().unwrap().to_os_string();
+    file_name.push(".tmp");
+    let tmp = path.with_file_name(file_name);
+    fs::write(&tmp, contents).unwrap();
+    fs::rename(&tmp, path).unwrap();
+}
+
+fn wait_until<F: Fn() -> bool>(f: F) {
+    let start = Instant::now();
+    while !f() && start.elapsed() < Duration::from_secs(5) {
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[crate::test]
+fn test_load_config() {
+    let path = temp_path("config.json");
+    replace(&path, r#"{ "port": 80 }"#);
+
+    let config = ConfigState::<Settings>::init::<JsonFormat>(ConfigSource::new(&path)).unwrap();
+    assert_eq!(config.load().port, 80);
+    assert_eq!(ConfigState::<Settings>::get().load().port, 80);
+    assert_eq!(SwapAppState::<Settings>::get().load().port, 80);
+    fs::remove_file(&path).unwrap();
+}
+
+#[crate::test]
+fn test_missing_file() {
+    let path = temp_path("missing-config.json");
+    let res = ConfigState::<Settings>::open::<JsonFormat>(ConfigSource::new(&path));
+
+    assert!(matches!(res, Err(StateError::Persistence { .. })));
+}
+
+#[crate::test]
+fn test_env_overrides() {
+    let path = temp_path("env-config.json");
+    replace(
+        &path,
+        r#"{ "port": 80, "database": { "url": "localhost" } }"#,
+    );
+    std::env::set_var("APP_STATE_ENV_TEST_PORT", "8080");
+    std::env::set_var("APP_STATE_ENV_TEST_DATABASE__URL", "remote");
+
+    let config = ConfigState::<Settings>::open::<JsonFormat>(
+        ConfigSource::new(&path).env_prefix("APP_STATE_ENV_TEST"),
+    )
+    .unwrap();
+    assert_eq!(
+        *config.load(),
+        Settings {
+            port: 8080,
+            database: Database {
+                url: "remote".to_string()
+            }
+        }
+    );
+    fs::remove_file(&path).unwrap();
+}
+
+#[crate::test]
+fn test_reload_publishes_new_version() {
+    let path = temp_path("reload-config.json");
+    replace(&path, r#"{ "port": 80 }"#);
+
+    let config = ConfigState::<Settings>::init::<JsonFormat>(ConfigSource::new(&path)).unwrap();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let events_clone = events.clone();
+    config.subscribe(move |event| {
+        if let ConfigEvent::Reloaded(settings) = event {
+            events_clone.lock().unwrap().push(settings.port);
+        }
+    });
+
+    let old = SwapAppState::<Settings>::get().load();
+    replace(&path, r#"{ "port": 8080 }"#);
+    assert_eq!(config.reload().unwrap().port, 8080);
+
+    // Readers holding the old version keep seeing it
+    assert_eq!(old.port, 80);
+    assert_eq!(SwapAppState::<Settings>::get().load().port, 8080);
+    assert_eq!(*events.lock().unwrap(), vec![8080]);
+    fs::remove_file(&path).unwrap();
+}
+
+#[crate::test]
+fn test_reject_invalid_file() {
+    let path = temp_path("invalid-config.json");
+    replace(&path, r#"{ "port": 80 }"#);
+
+    let config = ConfigState::<Settings>::open::<JsonFormat>(ConfigSource::new(&path)).unwrap();
+    let rejected = Arc::new(Mutex::new(0));
+    let rejected_clone = rejected.clone();
+    config.subscribe(move |event| {
+        if let ConfigEvent::Rejected(StateError::Deserialization { .. }) = event {
+            *rejected_clone.lock().unwrap() += 1;
+        }
+    });
+
+    replace(&path, r#"{ "port": "not a port" }"#);
+    assert!(config.reload().is_err());
+    assert_eq!(config.load().port, 80);
+    assert_eq!(*rejected.lock().unwrap(), 1);
+    assert!(matches!(
+        config.last_error(),
+        Some(StateError::Deserialization { .. })
+    ));
+    fs::remove_file(&path).unwrap();
+}
+
+#[crate::test]
+fn test_watch_file() {
+    let path = temp_path("watched-config.json");
+    replace(&path, r#"{ "port": 80 }"#);
+
+    let config = ConfigState::<Settings>::open::<JsonFormat>(
+        ConfigSource::new(&path).poll_interval(Duration::from_millis(10)),
+    )
+    .unwrap();
+
+    replace(&path, r#"{ "port": 8080 }"#);
+    wait_until(|| config.load().port == 8080);
+    assert_eq!(config.load().port, 8080);
+
+    replace(&path, "{ invalid }");
+    wait_until(|| config.last_error().is_some());
+    assert_eq!(config.load().port, 8080);
+
+    replace(&path, r#"{ "port": 443 }"#);
+    wait_until(|| config.load().port == 443);
+    assert_eq!(config.load().port, 443);
+    fs::remove_file(&path).unwrap();
+}
+
+#[crate::test]
+fn test_watch_same_length_edit() {
+    let path = temp_path("same-length-config.json");
+    replace(&path, r#"{ "port": 8080 }"#);
+    let modified = fs::metadata(&path).unwrap().modified().unwrap();
+
+    let config = ConfigState::<Settings>::open::<JsonFormat>(
+        ConfigSource::new(&path).poll_interval(Duration::from_millis(10)),
+    )
+    .unwrap();
+
+    // Neither the length nor the modification time of the file change
+    fs::write(&path, r#"{ "port": 9090 }"#).unwrap();
+    fs::File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+
+    wait_until(|| config.load().port == 9090);
+    assert_eq!(config.load().port, 9090);
+    fs::remove_file(&path).unwrap();
+}
+
+#[crate::test]
+fn test_stop_watching_on_shutdown() {
+    let path = temp_path("shutdown-config.json");
+    replace(&path, r#"{ "port": 80 }"#);
+
+    let config = ConfigState::<Settings>::init::<JsonFormat>(
+        ConfigSource::new(&path).poll_interval(Duration::from_millis(10)),
+    )
+    .unwrap();
+    shutdown();
+
+    replace(&path, r#"{ "port": 8080 }"#);
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(config.load().port, 80);
+    fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "toml")]
+#[crate::test]
+fn test_toml_config() {
+    let path = temp_path("config.toml");
+    replace(&path, "port = 80\n\n[database]\nurl = \"localhost\"\n");
+
+    let config =
+        ConfigState::<Settings>::open::<crate::TomlFormat>(ConfigSource::new(&path)).unwrap();
+    assert_eq!(config.load().database.url, "localhost");
+    fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "yaml")]
+#[crate::test]
+fn test_yaml_config() {
+    let path = temp_path("config.yaml");
+    replace(&path, "port: 80\ndatabase:\n  url: localhost\n");
+
+    let config =
+        ConfigState::<Settings>::open::<crate::YamlFormat>(ConfigSource::new(&path)).unwrap();
+    assert_eq!(config.load().port, 80);
+    assert_eq!(config.load().database.url, "localhost");
+    fs::remove_file(&path).unwrap();
+}
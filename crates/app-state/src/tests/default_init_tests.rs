@@ -1,5 +1,5 @@
-use crate::{init_default_state, AppState, AppStateTrait, MutAppState};
-use app_state_macros::{init_default_mut_state, stateful};
+use crate::{init_default_state, AppState, AppStateTrait, MutAppState, RwAppState};
+use app_state_macros::{init_default_mut_state, init_default_rw_state, stateful};
 
 #[init_default_state]
 struct State {
@@ -27,6 +27,19 @@ impl Default for MutState {
     }
 }
 
+#[init_default_rw_state]
+struct RwState {
+    name: String,
+}
+
+impl Default for RwState {
+    fn default() -> Self {
+        Self {
+            name: "Hello".to_string(),
+        }
+    }
+}
+
 #[init_default_state]
 #[init_default_mut_state]
 struct BothState {
@@ -103,6 +116,22 @@ fn test_injected_non_existent_mut_state() {
     check_mut_state();
 }
 
+#[test]
+fn test_get_rw_state() {
+    let rw_state = RwAppState::<RwState>::get();
+    assert_eq!(rw_state.read().name, "Hello".to_string());
+}
+
+#[test]
+fn test_inject_rw_state() {
+    #[stateful]
+    fn check_rw_state(rw_state: RwAppState<RwState>) {
+        assert_eq!(rw_state.read().name, "Hello".to_string());
+    }
+
+    check_rw_state();
+}
+
 #[test]
 fn test_get_both_state() {
     let state = AppState::<BothState>::get();
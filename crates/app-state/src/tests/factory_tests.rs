@@ -0,0 +1,174 @@
+use crate::{
+    initialize_all, register_factory, AppState, AppStateTrait, Dependency, InitAppState,
+    InitMutAppState, MutAppState, StateError, StateFactory, StateStore,
+};
+
+struct Config {
+    url: String,
+}
+
+struct Db {
+    url: String,
+}
+
+struct Service {
+    description: String,
+}
+
+fn config_factory() -> StateFactory {
+    StateFactory::new::<AppState<_>, _>(vec![], |_| Config {
+        url: "localhost".to_string(),
+    })
+}
+
+fn db_factory() -> StateFactory {
+    StateFactory::new::<MutAppState<_>, _>(vec![Dependency::of::<AppState<Config>>()], |store| Db {
+        url: store.get::<AppState<Config>>().url.clone(),
+    })
+}
+
+fn service_factory() -> StateFactory {
+    StateFactory::new::<AppState<_>, _>(
+        vec![
+            Dependency::of::<AppState<Config>>(),
+            Dependency::of::<MutAppState<Db>>(),
+        ],
+        |store| Service {
+            description: format!(
+                "{} via {}",
+                store.get::<AppState<Config>>().url,
+                store.get::<MutAppState<Db>>().get_mut().url
+            ),
+        },
+    )
+}
+
+#[crate::test]
+fn test_initialize_in_dependency_order() {
+    register_factory(service_factory());
+    register_factory(db_factory());
+    register_factory(config_factory());
+
+    initialize_all().unwrap();
+    assert_eq!(
+        AppState::<Service>::get().description,
+        "localhost via localhost"
+    );
+    assert_eq!(MutAppState::<Db>::get().get_mut().url, "localhost");
+}
+
+#[crate::test]
+fn test_initialized_dependency() {
+    AppState::init(Config {
+        url: "remote".to_string(),
+    });
+    register_factory(db_factory());
+
+    initialize_all().unwrap();
+    assert_eq!(MutAppState::<Db>::get().get_mut().url, "remote");
+}
+
+#[crate::test]
+fn test_initialize_all_keeps_existing_states() {
+    register_factory(config_factory());
+    initialize_all().unwrap();
+
+    AppState::init(Config {
+        url: "remote".to_string(),
+    });
+    initialize_all().unwrap();
+    assert_eq!(AppState::<Config>::get().url, "remote");
+}
+
+#[crate::test]
+fn test_missing_dependency() {
+    register_factory(service_factory());
+    register_factory(config_factory());
+
+    assert_eq!(
+        initialize_all(),
+        Err(StateError::MissingDependency {
+            type_name: std::any::type_name::<AppState<Service>>(),
+            dependency: std::any::type_name::<MutAppState<Db>>(),
+        })
+    );
+    // Nothing is built if the dependencies can not be satisfied
+    assert!(AppState::<Config>::try_get().is_err());
+}
+
+#[test]
+fn test_dependency_cycle() {
+    let store = StateStore::new();
+    store.register_factory(StateFactory::new::<AppState<_>, _>(
+        vec![Dependency::of::<MutAppState<Db>>()],
+        |store| Config {
+            url: store.get::<MutAppState<Db>>().get_mut().url.clone(),
+        },
+    ));
+    store.register_factory(db_factory());
+
+    assert_eq!(
+        store.initialize_all(),
+        Err(StateError::DependencyCycle {
+            type_names: vec![
+                std::any::type_name::<AppState<Config>>(),
+                std::any::type_name::<MutAppState<Db>>(),
+                std::any::type_name::<AppState<Config>>(),
+            ],
+        })
+    );
+    assert!(store.try_get::<AppState<Config>>().is_err());
+}
+
+#[derive(InitAppState)]
+#[app_state(depends_on())]
+struct DerivedConfig {
+    url: String,
+}
+
+impl DerivedConfig {
+    fn new() -> Self {
+        DerivedConfig {
+            url: "derived".to_string(),
+        }
+    }
+}
+
+#[derive(InitMutAppState)]
+#[app_state(depends_on(DerivedConfig))]
+struct DerivedClient {
+    url: String,
+}
+
+impl DerivedClient {
+    fn new(config: AppState<DerivedConfig>) -> Self {
+        DerivedClient {
+            url: config.url.clone(),
+        }
+    }
+}
+
+#[derive(InitAppState)]
+#[app_state(depends_on(MutAppState<DerivedClient>, DerivedConfig))]
+struct DerivedService {
+    description: String,
+}
+
+impl DerivedService {
+    fn new(client: MutAppState<DerivedClient>, config: AppState<DerivedConfig>) -> Self {
+        DerivedService {
+            description: format!("{} and {}", client.get_mut().url, config.url),
+        }
+    }
+}
+
+#[crate::test]
+fn test_derived_factories() {
+    initialize_all().unwrap();
+
+    assert_eq!(MutAppState::<DerivedClient>::get().get_mut().url, "derived");
+    assert_eq!(
+        AppState::<DerivedService>::get().description,
+        "derived and derived"
+    );
+}
@@ -1,6 +1,8 @@
-use crate::{AppState, AppStateTrait, InitAppState, InitMutAppState, MutAppState};
+use crate::{
+    AppState, AppStateTrait, InitAppState, InitMutAppState, InitRwAppState, MutAppState, RwAppState,
+};
 
-#[derive(InitAppState, InitMutAppState)]
+#[derive(InitAppState, InitMutAppState, InitRwAppState)]
 struct State {
     name: String,
 }
@@ -34,3 +36,15 @@ fn test_init_mut_state() {
         "Changed".to_string()
     );
 }
+
+#[test]
+fn test_init_rw_state() {
+    State::default().init_rw_app_state();
+    let state = RwAppState::<State>::get();
+    state.write().name = "Changed".to_string();
+
+    assert_eq!(
+        RwAppState::<State>::get().read().name,
+        "Changed".to_string()
+    );
+}
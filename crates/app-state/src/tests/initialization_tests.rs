@@ -0,0 +1,214 @@
+use crate::{AppState, AppStateTrait, MutAppState, StateError, StateStore};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::Duration;
+
+struct Config {
+    url: String,
+}
+
+struct Client {
+    url: String,
+}
+
+#[crate::test]
+fn test_initializer_reads_other_state() {
+    AppState::init(Config {
+        url: "localhost".to_string(),
+    });
+
+    let client = AppState::get_or_insert_with(|| Client {
+        url: AppState::<Config>::get().url.clone(),
+    });
+    assert_eq!(client.url, "localhost");
+}
+
+#[crate::test]
+fn test_nested_initializers() {
+    let client = MutAppState::get_or_insert_with(|| {
+        let config = AppState::get_or_insert_with(|| Config {
+            url: "localhost".to_string(),
+        });
+
+        Client {
+            url: config.url.clone(),
+        }
+    });
+
+    assert_eq!(client.get_mut().url, "localhost");
+    assert_eq!(AppState::<Config>::get().url, "localhost");
+}
+
+#[crate::test]
+fn test_self_initialization_cycle() {
+    let res = AppState::<Config>::try_get_or_insert_with(|| {
+        let inner = AppState::<Config>::try_get_or_insert_with(|| Config {
+            url: "inner".to_string(),
+        });
+        assert!(matches!(
+            inner,
+            Err(StateError::InitializationCycle { name: None, .. })
+        ));
+
+        Config {
+            url: "outer".to_string(),
+        }
+    });
+
+    assert_eq!(res.unwrap().url, "outer");
+    assert_eq!(AppState::<Config>::get().url, "outer");
+}
+
+#[crate::test]
+fn test_initialization_cycle_between_states() {
+    let config = AppState::<Config>::get_or_insert_with(|| {
+        let client = AppState::<Client>::get_or_insert_with(|| {
+            let config = AppState::<Config>::try_get_or_insert_with(|| Config {
+                url: "inner".to_string(),
+            });
+            assert!(matches!(
+                config,
+                Err(StateError::InitializationCycle { .. })
+            ));
+
+            Client {
+                url: "client".to_string(),
+            }
+        });
+
+        Config {
+            url: client.url.clone(),
+        }
+    });
+
+    assert_eq!(config.url, "client");
+    assert_eq!(AppState::<Client>::get().url, "client");
+}
+
+#[crate::test]
+#[should_panic(expected = "Cyclic dependency while initializing state")]
+fn test_initialization_cycle_panics() {
+    AppState::<Config>::get_or_insert_with(|| Config {
+        url: AppState::<Config>::get_or_insert_with(|| Config {
+            url: "inner".to_string(),
+        })
+        .url
+        .clone(),
+    });
+}
+
+#[crate::test]
+fn test_named_initialization_cycle() {
+    let res = AppState::<Config>::try_get_or_insert_with_named("primary", || {
+        let inner = AppState::<Config>::try_get_or_insert_with_named("primary", || Config {
+            url: "inner".to_string(),
+        });
+        assert_eq!(
+            inner.err(),
+            Some(StateError::InitializationCycle {
+                type_name: std::any::type_name::<AppState<Config>>(),
+                name: Some("primary".to_string()),
+            })
+        );
+
+        // Other names of the same type are independent
+        let replica = AppState::<Config>::get_or_insert_with_named("replica", || Config {
+            url: "replica".to_string(),
+        });
+        Config {
+            url: format!("primary of {}", replica.url),
+        }
+    });
+
+    assert_eq!(res.unwrap().url, "primary of replica");
+}
+
+#[test]
+fn test_concurrent_initialization_runs_once() {
+    let store = Arc::new(StateStore::new());
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let handles = (0..4)
+        .map(|_| {
+            let store = store.clone();
+            let calls = calls.clone();
+            thread::spawn(move || {
+                store.get_or_insert_with::<AppState<_>, _, _>(|| {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(50));
+                    Config {
+                        url: "localhost".to_string(),
+                    }
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        assert_eq!(handle.join().unwrap().url, "localhost");
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_initialization_cycle_between_threads() {
+    let store = Arc::new(StateStore::new());
+    let barrier = Arc::new(Barrier::new(2));
+    let cycles = Arc::new(AtomicUsize::new(0));
+
+    let config_thread = {
+        let store = store.clone();
+        let barrier = barrier.clone();
+        let cycles = cycles.clone();
+        thread::spawn(move || {
+            store.get_or_insert_with::<AppState<_>, _, _>(|| {
+                barrier.wait();
+                let client = store.try_get_or_insert_with::<AppState<_>, _, _>(|| Client {
+                    url: "config".to_string(),
+                });
+                if client.is_err() {
+                    cycles.fetch_add(1, Ordering::SeqCst);
+                }
+
+                Config {
+                    url: "config".to_string(),
+                }
+            })
+        })
+    };
+
+    store.get_or_insert_with::<AppState<_>, _, _>(|| {
+        barrier.wait();
+        let config = store.try_get_or_insert_with::<AppState<_>, _, _>(|| Config {
+            url: "client".to_string(),
+        });
+        if config.is_err() {
+            cycles.fetch_add(1, Ordering::SeqCst);
+        }
+
+        Client {
+            url: "client".to_string(),
+        }
+    });
+    config_thread.join().unwrap();
+
+    // Exactly one of the threads detects the cycle, the other one waits for it
+    assert_eq!(cycles.load(Ordering::SeqCst), 1);
+    assert_eq!(store.get::<AppState<Config>>().url, "config");
+    assert_eq!(store.get::<AppState<Client>>().url, "client");
+}
+
+#[test]
+fn test_panicking_initializer() {
+    let store = StateStore::new();
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        store.get_or_insert_with::<AppState<Config>, _, _>(|| panic!("Failed"));
+    }));
+    assert!(res.is_err());
+
+    let config = store.get_or_insert_with::<AppState<_>, _, _>(|| Config {
+        url: "localhost".to_string(),
+    });
+    assert_eq!(config.url, "localhost");
+}
@@ -0,0 +1,102 @@
+use crate::tests::util::StateTrait;
+use crate::{AppState, AppStateTrait, MutAppState, StateStore};
+use std::thread;
+
+struct State {
+    name: String,
+}
+
+impl StateTrait for State {
+    fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+}
+
+#[crate::test]
+fn test_isolated_init() {
+    AppState::init(State {
+        name: "First".to_string(),
+    });
+    assert_eq!(AppState::<State>::get().get_name(), "First");
+}
+
+#[crate::test]
+fn test_isolated_init_same_type() {
+    AppState::init(State {
+        name: "Second".to_string(),
+    });
+    MutAppState::init(State {
+        name: "Second".to_string(),
+    });
+
+    MutAppState::<State>::get().get_mut().set_name("Changed");
+    assert_eq!(AppState::<State>::get().get_name(), "Second");
+    assert_eq!(MutAppState::<State>::get().get_mut().get_name(), "Changed");
+}
+
+#[crate::test]
+#[should_panic]
+fn test_isolated_state_not_found() {
+    MutAppState::<State>::get();
+}
+
+#[test]
+fn test_isolated_guard_drop() {
+    struct Isolated;
+
+    let guard = StateStore::isolated();
+    AppState::init(Isolated);
+    assert!(AppState::<Isolated>::try_get().is_ok());
+    assert!(StateStore::global()
+        .try_get::<AppState<Isolated>>()
+        .is_err());
+
+    drop(guard);
+    assert!(AppState::<Isolated>::try_get().is_err());
+}
+
+#[test]
+fn test_nested_isolation() {
+    struct Nested(u32);
+
+    let outer = StateStore::isolated();
+    AppState::init(Nested(1));
+
+    let inner = StateStore::isolated();
+    assert_eq!(AppState::<Nested>::get().0, 1);
+    AppState::init(Nested(2));
+    assert_eq!(AppState::<Nested>::get().0, 2);
+
+    drop(inner);
+    assert_eq!(AppState::<Nested>::get().0, 1);
+    drop(outer);
+    assert!(AppState::<Nested>::try_get().is_err());
+}
+
+#[test]
+fn test_isolated_falls_back_to_global() {
+    struct Global(u32);
+
+    AppState::init(Global(1));
+    let _guard = StateStore::isolated();
+    assert_eq!(AppState::<Global>::get().0, 1);
+    assert_eq!(AppState::<Global>::get_or_insert(Global(2)).0, 1);
+
+    AppState::init(Global(3));
+    assert_eq!(AppState::<Global>::get().0, 3);
+    assert_eq!(StateStore::global().get::<AppState<Global>>().0, 1);
+}
+
+#[crate::test]
+fn test_isolation_is_thread_local() {
+    struct Local;
+
+    AppState::init(Local);
+    thread::spawn(|| assert!(AppState::<Local>::try_get().is_err()))
+        .join()
+        .unwrap();
+}
@@ -0,0 +1,171 @@
+use crate::{
+    init_default_mut_state, shutdown, AppState, AppStateTrait, MutAppState, PoisonPolicy,
+    StateLifecycle, StateStore,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct Events(Mutex<Vec<String>>);
+
+impl Events {
+    fn push(&self, event: String) {
+        self.0.lock().unwrap().push(event);
+    }
+
+    fn get(&self) -> Vec<String> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+struct Resource {
+    name: &'static str,
+}
+
+impl StateLifecycle for Resource {
+    fn on_init(&self) {
+        AppState::<Events>::get().push(format!("init {}", self.name));
+    }
+
+    fn on_shutdown(&self) {
+        AppState::<Events>::get().push(format!("shutdown {}", self.name));
+    }
+}
+
+struct Pool {
+    connections: u32,
+}
+
+impl StateLifecycle for Pool {
+    fn on_shutdown(&self) {
+        // Other states are still available during shutdown
+        let resource = AppState::<Resource>::get();
+        AppState::<Events>::get().push(format!(
+            "close {} connections of {}",
+            self.connections, resource.name
+        ));
+    }
+}
+
+#[crate::test]
+fn test_shutdown_in_reverse_order() {
+    let events = AppState::<Events>::get_or_insert_default();
+    AppState::init_with_lifecycle(Resource { name: "first" });
+    MutAppState::init_with_lifecycle(Resource { name: "second" });
+    AppState::init_with_lifecycle(Pool { connections: 2 });
+
+    shutdown();
+    assert_eq!(
+        events.get(),
+        vec![
+            "init first",
+            "init second",
+            "close 2 connections of first",
+            "shutdown second",
+            "shutdown first",
+        ]
+    );
+    assert!(AppState::<Resource>::try_get().is_err());
+    assert!(MutAppState::<Resource>::try_get().is_err());
+    assert!(AppState::<Events>::try_get().is_err());
+}
+
+#[crate::test]
+fn test_reinitialized_state_shuts_down_last() {
+    let events = AppState::<Events>::get_or_insert_default();
+    AppState::init_with_lifecycle(Resource { name: "first" });
+    MutAppState::init_with_lifecycle(Resource { name: "second" });
+    AppState::init_with_lifecycle(Resource { name: "third" });
+
+    shutdown();
+    assert_eq!(
+        events.get(),
+        vec![
+            "init first",
+            "init second",
+            "init third",
+            "shutdown third",
+            "shutdown second",
+        ]
+    );
+}
+
+#[crate::test]
+fn test_removed_state_is_not_shut_down() {
+    let events = AppState::<Events>::get_or_insert_default();
+    AppState::init_with_lifecycle(Resource { name: "first" });
+    assert!(AppState::<Resource>::remove().is_some());
+
+    shutdown();
+    assert_eq!(events.get(), vec!["init first"]);
+}
+
+#[crate::test]
+fn test_overwritten_state_is_not_shut_down() {
+    let events = AppState::<Events>::get_or_insert_default();
+    AppState::init_with_lifecycle(Resource { name: "first" });
+    AppState::init(Resource { name: "second" });
+
+    shutdown();
+    assert_eq!(events.get(), vec!["init first"]);
+}
+
+#[crate::test]
+fn test_poisoned_state_does_not_abort_shutdown() {
+    let events = AppState::<Events>::get_or_insert_default();
+    AppState::init_with_lifecycle(Resource { name: "first" });
+    MutAppState::init_with_lifecycle(Resource { name: "second" });
+
+    let state = MutAppState::<Resource>::get();
+    state.set_poison_policy(PoisonPolicy::ReturnError);
+    std::thread::spawn(move || {
+        let _lock = state.get_mut();
+        panic!("Poisoning the state");
+    })
+    .join()
+    .unwrap_err();
+
+    shutdown();
+    assert_eq!(
+        events.get(),
+        vec!["init first", "init second", "shutdown first"]
+    );
+    assert!(MutAppState::<Resource>::try_get().is_err());
+}
+
+struct Counter(Arc<AtomicUsize>);
+
+impl StateLifecycle for Counter {
+    fn on_shutdown(&self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_shutdown_store() {
+    let shutdowns = Arc::new(AtomicUsize::new(0));
+    let store = StateStore::new();
+    store.init_with_lifecycle::<MutAppState<_>, _>(Counter(shutdowns.clone()));
+    store.shutdown();
+
+    assert_eq!(shutdowns.load(Ordering::SeqCst), 1);
+    assert!(store.try_get::<MutAppState<Counter>>().is_err());
+}
+
+static DEFAULT_INITIALIZED: AtomicUsize = AtomicUsize::new(0);
+
+#[init_default_mut_state]
+#[derive(Default)]
+struct DefaultResource;
+
+impl StateLifecycle for DefaultResource {
+    fn on_init(&self) {
+        DEFAULT_INITIALIZED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_default_state_lifecycle() {
+    assert_eq!(DEFAULT_INITIALIZED.load(Ordering::SeqCst), 1);
+    assert!(MutAppState::<DefaultResource>::try_get().is_ok());
+}
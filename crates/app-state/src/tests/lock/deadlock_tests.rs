@@ -0,0 +1,143 @@
+use crate::{stateful, AppStateTrait, MutAppState, MutAppStateLock, PoisonPolicy};
+use std::any::Any;
+
+#[derive(Default)]
+struct First;
+
+#[derive(Default)]
+struct Second;
+
+#[derive(Default)]
+struct Third;
+
+fn panic_message(err: Box<dyn Any + Send>) -> String {
+    err.downcast::<String>()
+        .map(|message| *message)
+        .unwrap_or_default()
+}
+
+#[stateful]
+fn inner(_state: MutAppStateLock<First>) {}
+
+#[stateful(init(_state))]
+fn outer(_state: MutAppStateLock<First>) {
+    inner();
+}
+
+#[crate::test]
+fn test_relock_in_nested_function() {
+    let message = panic_message(std::panic::catch_unwind(outer).unwrap_err());
+
+    assert!(message.starts_with("Deadlock detected"), "{message}");
+    assert!(message.contains("deadlock_tests::inner"), "{message}");
+    assert!(message.contains("deadlock_tests::outer"), "{message}");
+    assert!(
+        message.contains(std::any::type_name::<First>()),
+        "{message}"
+    );
+}
+
+#[crate::test]
+fn test_relock_on_same_thread() {
+    let state = MutAppState::<First>::get_or_insert_default();
+    let lock = state.get_mut();
+
+    let cloned = state.clone();
+    let res = std::panic::catch_unwind(move || {
+        cloned.get_mut();
+    });
+    drop(lock);
+
+    let message = panic_message(res.unwrap_err());
+    assert!(message.contains("same thread"), "{message}");
+    assert!(message.contains(file!()), "{message}");
+
+    // The state can be locked again once it has been unlocked
+    drop(state.get_mut());
+    assert!(state.try_get_mut().is_some());
+}
+
+#[crate::test]
+fn test_lock_order_inversion() {
+    let first = MutAppState::<First>::get_or_insert_default();
+    let second = MutAppState::<Second>::get_or_insert_default();
+
+    // The order is recorded even though the threads never wait for each other
+    {
+        let (first, second) = (first.clone(), second.clone());
+        std::thread::spawn(move || {
+            let _first = first.get_mut();
+            let _second = second.get_mut();
+        })
+        .join()
+        .unwrap();
+    }
+
+    // The panicking thread poisons the state it holds
+    second.set_poison_policy(PoisonPolicy::Recover);
+    let cloned = (first.clone(), second.clone());
+    let res = std::panic::catch_unwind(move || {
+        let _second = cloned.1.get_mut();
+        let _first = cloned.0.get_mut();
+    });
+
+    let message = panic_message(res.unwrap_err());
+    assert!(message.starts_with("Deadlock detected"), "{message}");
+    assert!(message.contains("opposite order"), "{message}");
+    assert!(
+        message.contains(std::any::type_name::<First>()),
+        "{message}"
+    );
+    assert!(
+        message.contains(std::any::type_name::<Second>()),
+        "{message}"
+    );
+
+    // Locking the states in the recorded order is still possible
+    let _first = first.get_mut();
+    let _second = second.get_mut();
+}
+
+#[crate::test]
+fn test_lock_order_inversion_through_other_state() {
+    let first = MutAppState::<First>::get_or_insert_default();
+    let second = MutAppState::<Second>::get_or_insert_default();
+    let third = MutAppState::<Third>::get_or_insert_default();
+
+    drop(lock_all_in_order(&first, &second));
+    drop(lock_all_in_order(&second, &third));
+
+    let res = std::panic::catch_unwind(move || {
+        let _third = third.get_mut();
+        let _first = first.get_mut();
+    });
+
+    let message = panic_message(res.unwrap_err());
+    assert!(message.starts_with("Deadlock detected"), "{message}");
+    assert!(
+        message.contains(std::any::type_name::<Second>()),
+        "{message}"
+    );
+}
+
+#[crate::test]
+fn test_try_lock_does_not_record_order() {
+    let first = MutAppState::<First>::get_or_insert_default();
+    let second = MutAppState::<Second>::get_or_insert_default();
+
+    {
+        let _second = second.get_mut();
+        let _first = first.try_get_mut().unwrap();
+    }
+
+    let _first = first.get_mut();
+    let _second = second.get_mut();
+}
+
+fn lock_all_in_order<'a, A: Send + 'static, B: Send + 'static>(
+    first: &'a MutAppState<A>,
+    second: &'a MutAppState<B>,
+) -> (MutAppStateLock<'a, A>, MutAppStateLock<'a, B>) {
+    let first = first.get_mut();
+    (first, second.get_mut())
+}
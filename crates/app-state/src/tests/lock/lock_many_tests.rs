@@ -0,0 +1,126 @@
+use crate::{lock_all, stateful, AppStateTrait, MutAppState, MutAppStateLock};
+use std::thread;
+
+#[derive(Default)]
+struct Balance(u32);
+
+#[derive(Default)]
+struct Audit(Vec<u32>);
+
+#[derive(Default)]
+struct Total(u32);
+
+/// Only used in the global state store
+struct Account(u32);
+
+/// Only used in the global state store
+#[derive(Default)]
+struct Ledger(Vec<u32>);
+
+#[stateful]
+fn deposit(mut account: MutAppStateLock<Account>, mut ledger: MutAppStateLock<Ledger>) {
+    account.0 += 1;
+    ledger.0.push(account.0);
+}
+
+#[stateful]
+fn withdraw(mut ledger: MutAppStateLock<Ledger>, mut account: MutAppStateLock<Account>) {
+    account.0 -= 1;
+    ledger.0.push(account.0);
+}
+
+#[stateful(init(total), timeout(total = "1s"))]
+fn deposit_with_total(
+    mut total: MutAppStateLock<Total>,
+    balance: MutAppStateLock<Balance>,
+    audit: MutAppStateLock<Audit>,
+) {
+    total.0 += balance.0 + audit.0.len() as u32;
+}
+
+#[crate::test]
+fn test_lock_many() {
+    let balance = MutAppState::<Balance>::get_or_insert_default();
+    let audit = MutAppState::<Audit>::get_or_insert_default();
+
+    let (mut audit_lock, mut balance_lock) = MutAppState::lock_many((&audit, &balance));
+    balance_lock.0 = 10;
+    audit_lock.0.push(10);
+    drop((audit_lock, balance_lock));
+
+    let (balance, audit) = lock_all!(balance, audit);
+    assert_eq!(balance.0, 10);
+    assert_eq!(audit.0, vec![10]);
+}
+
+#[crate::test]
+fn test_lock_many_single_state() {
+    let balance = MutAppState::<Balance>::get_or_insert_default();
+
+    let (mut lock,) = lock_all!(balance);
+    lock.0 += 1;
+    drop(lock);
+
+    assert_eq!(balance.get_mut().0, 1);
+}
+
+#[crate::test]
+fn test_lock_many_opposite_order() {
+    let balance = MutAppState::<Balance>::get_or_insert_default();
+    let audit = MutAppState::<Audit>::get_or_insert_default();
+
+    let handle = {
+        let (balance, audit) = (balance.clone(), audit.clone());
+        thread::spawn(move || {
+            for _ in 0..1000 {
+                let (mut balance, _audit) = lock_all!(balance, audit);
+                balance.0 += 1;
+            }
+        })
+    };
+
+    for _ in 0..1000 {
+        let (_audit, mut balance) = lock_all!(audit, balance);
+        balance.0 += 1;
+    }
+
+    handle.join().unwrap();
+    assert_eq!(balance.get_mut().0, 2000);
+}
+
+#[test]
+fn test_injected_locks_opposite_order() {
+    MutAppState::init(Account(1000));
+    MutAppState::init(Ledger::default());
+
+    let handle = thread::spawn(|| {
+        for _ in 0..1000 {
+            deposit();
+        }
+    });
+
+    for _ in 0..1000 {
+        withdraw();
+    }
+
+    handle.join().unwrap();
+    assert_eq!(MutAppState::<Account>::get().get_mut().0, 1000);
+    assert_eq!(MutAppState::<Ledger>::get().get_mut().0.len(), 2000);
+}
+
+#[crate::test]
+fn test_injected_locks_with_timeout() {
+    MutAppState::init(Balance(2));
+    MutAppState::init(Audit(vec![1]));
+
+    deposit_with_total();
+    assert_eq!(MutAppState::<Total>::get().get_mut().0, 3);
+}
+
+#[cfg(feature = "deadlock-detection")]
+#[crate::test]
+#[should_panic(expected = "Deadlock detected")]
+fn test_lock_same_state_twice() {
+    let balance = MutAppState::<Balance>::get_or_insert_default();
+    let _locks = lock_all!(balance, balance);
+}
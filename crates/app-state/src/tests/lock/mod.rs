@@ -1 +1,5 @@
+#[cfg(feature = "deadlock-detection")]
+mod deadlock_tests;
 mod injection_tests;
+mod lock_many_tests;
+mod timeout_tests;
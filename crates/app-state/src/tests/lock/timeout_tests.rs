@@ -0,0 +1,100 @@
+use crate::{stateful, AppStateTrait, MutAppState, MutAppStateLock, StateError};
+use std::time::{Duration, Instant};
+
+#[derive(Default)]
+struct Counter {
+    value: u32,
+}
+
+#[stateful(timeout(counter = "20ms"))]
+fn increment(mut counter: MutAppStateLock<Counter>) -> Result<u32, StateError> {
+    counter.value += 1;
+    Ok(counter.value)
+}
+
+#[stateful(init(counter), timeout(counter = "20ms"))]
+fn increment_or_panic(mut counter: MutAppStateLock<Counter>) -> u32 {
+    counter.value += 1;
+    counter.value
+}
+
+#[crate::test]
+fn test_try_get_mut() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    let lock = state.try_get_mut().unwrap();
+
+    assert!(state.try_get_mut().is_none());
+    drop(lock);
+    assert!(state.try_get_mut().is_some());
+}
+
+#[crate::test]
+fn test_try_lock() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    let lock = state.get_mut();
+
+    assert_eq!(
+        MutAppStateLock::try_lock(&state).err(),
+        Some(StateError::WouldBlock {
+            type_name: std::any::type_name::<Counter>(),
+        })
+    );
+    drop(lock);
+    assert!(MutAppStateLock::try_lock(&state).is_ok());
+}
+
+#[crate::test]
+fn test_get_mut_timeout() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    let lock = state.get_mut();
+
+    let start = Instant::now();
+    assert!(matches!(
+        state.get_mut_timeout(Duration::from_millis(20)).err(),
+        Some(StateError::LockTimeout { .. })
+    ));
+    assert!(start.elapsed() >= Duration::from_millis(20));
+
+    drop(lock);
+    assert!(state.get_mut_timeout(Duration::from_millis(20)).is_ok());
+}
+
+#[crate::test]
+fn test_get_mut_timeout_waits_for_lock() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    let cloned = state.clone();
+    let (locked, wait_locked) = std::sync::mpsc::channel();
+
+    let handle = std::thread::spawn(move || {
+        let mut lock = cloned.get_mut();
+        locked.send(()).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        lock.value = 5;
+    });
+
+    wait_locked.recv().unwrap();
+    let lock = state.get_mut_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(lock.value, 5);
+    handle.join().unwrap();
+}
+
+#[crate::test]
+fn test_stateful_timeout_returns_error() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    assert_eq!(increment(), Ok(1));
+
+    let lock = state.get_mut();
+    assert!(matches!(increment(), Err(StateError::LockTimeout { .. })));
+    drop(lock);
+    assert_eq!(increment(), Ok(2));
+}
+
+#[crate::test]
+fn test_stateful_timeout_panics() {
+    assert_eq!(increment_or_panic(), 1);
+
+    let state = MutAppState::<Counter>::get();
+    let lock = state.get_mut();
+    assert!(std::panic::catch_unwind(increment_or_panic).is_err());
+    drop(lock);
+}
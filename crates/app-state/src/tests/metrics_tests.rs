@@ -0,0 +1,172 @@
+use crate::{stateful, stats, AppState, AppStateTrait, MutAppState, MutAppStateLock};
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::Duration;
+
+struct Config;
+
+// Metrics are global, hence every test uses its own states
+
+#[derive(Default)]
+struct Counter(u32);
+
+#[derive(Default)]
+struct Busy;
+
+#[derive(Default)]
+struct Exported(u32);
+
+#[derive(Default)]
+struct Contended;
+
+#[derive(Default)]
+struct Injected(u32);
+
+#[derive(Default)]
+struct Unused;
+
+struct Separate(u32);
+
+#[stateful(init(injected))]
+fn increment(mut injected: MutAppStateLock<Injected>) {
+    injected.0 += 1;
+}
+
+#[crate::test]
+fn test_count_gets() {
+    AppState::init(Config);
+    AppState::<Config>::get();
+    AppState::<Config>::try_get().unwrap();
+    assert!(AppState::<Config>::try_get_named("missing").is_err());
+
+    let stats = stats();
+    let config = stats.get::<AppState<Config>>().unwrap();
+    assert_eq!(config.gets, 2);
+    assert_eq!(config.lock_acquisitions, 0);
+    let missing = stats.get_named::<AppState<Config>>("missing").unwrap();
+    assert_eq!(missing.gets, 1);
+    assert!(stats.get::<AppState<Unused>>().is_none());
+}
+
+#[crate::test]
+fn test_count_locks() {
+    let counter = MutAppState::<Counter>::get_or_insert_default();
+    counter.get_mut().0 += 1;
+    counter.try_get_mut().unwrap().0 += 1;
+    counter
+        .get_mut_timeout(Duration::from_millis(10))
+        .unwrap()
+        .0 += 1;
+
+    let stats = stats();
+    let counter = stats.get::<MutAppState<Counter>>().unwrap();
+    assert_eq!(counter.gets, 1);
+    assert_eq!(counter.lock_acquisitions, 3);
+    assert!(counter.max_hold_time <= counter.total_hold_time);
+    assert!(counter.max_wait_time <= counter.total_wait_time);
+}
+
+#[crate::test]
+fn test_failed_lock_is_not_counted() {
+    let counter = MutAppState::<Busy>::get_or_insert_default();
+    let _lock = counter.get_mut();
+    assert!(counter.try_get_mut().is_none());
+
+    assert_eq!(
+        stats()
+            .get::<MutAppState<Busy>>()
+            .unwrap()
+            .lock_acquisitions,
+        1
+    );
+}
+
+#[crate::test]
+fn test_wait_and_hold_time() {
+    let state = MutAppState::<Contended>::get_or_insert_default();
+    let barrier = Arc::new(Barrier::new(2));
+
+    let handle = {
+        let (state, barrier) = (state.clone(), barrier.clone());
+        thread::spawn(move || {
+            let _lock = state.get_mut();
+            barrier.wait();
+            thread::sleep(Duration::from_millis(50));
+        })
+    };
+
+    barrier.wait();
+    drop(state.get_mut());
+    handle.join().unwrap();
+
+    let stats = stats();
+    let contended = stats.get::<MutAppState<Contended>>().unwrap();
+    assert_eq!(contended.lock_acquisitions, 2);
+    assert!(contended.max_wait_time >= Duration::from_millis(40));
+    assert!(contended.max_hold_time >= Duration::from_millis(50));
+    assert!(contended.total_hold_time >= contended.max_hold_time);
+}
+
+#[crate::test]
+fn test_injected_state() {
+    increment();
+    increment();
+
+    let stats = stats();
+    let injected = stats.get::<MutAppState<Injected>>().unwrap();
+    assert_eq!(injected.gets, 2);
+    assert_eq!(injected.lock_acquisitions, 2);
+}
+
+#[crate::test]
+fn test_prometheus_format() {
+    MutAppState::<Exported>::get_or_insert_default().get_mut().0 += 1;
+
+    let metrics = stats().to_prometheus();
+    let name = std::any::type_name::<Exported>();
+
+    assert!(metrics.contains("# TYPE app_state_gets_total counter\n"));
+    assert!(metrics.contains("# TYPE app_state_lock_wait_seconds_max gauge\n"));
+    assert!(metrics.contains(&format!(
+        "app_state_gets_total{{state=\"{name}\",kind=\"MutAppState\"}} 1\n"
+    )));
+    assert!(metrics.contains(&format!(
+        "app_state_lock_acquisitions_total{{state=\"{name}\",kind=\"MutAppState\"}} 1\n"
+    )));
+    assert!(metrics.contains(&format!(
+        "app_state_lock_hold_seconds_total{{state=\"{name}\",kind=\"MutAppState\"}} "
+    )));
+}
+
+#[crate::test]
+fn test_kinds_and_names_are_separate() {
+    AppState::init(Separate(0));
+    MutAppState::init(Separate(1));
+    MutAppState::init_named("other", Separate(2));
+
+    AppState::<Separate>::get();
+    MutAppState::<Separate>::get().get_mut().0 += 1;
+    let other = MutAppState::<Separate>::get_named("other");
+    other.get_mut().0 += 1;
+    other.get_mut().0 += 1;
+
+    let stats = stats();
+    let immutable = stats.get::<AppState<Separate>>().unwrap();
+    assert_eq!((immutable.gets, immutable.lock_acquisitions), (1, 0));
+    let mutable = stats.get::<MutAppState<Separate>>().unwrap();
+    assert_eq!((mutable.gets, mutable.lock_acquisitions), (1, 1));
+    let named = stats.get_named::<MutAppState<Separate>>("other").unwrap();
+    assert_eq!((named.gets, named.lock_acquisitions), (1, 2));
+
+    let (key, _) = stats
+        .iter()
+        .find(|(key, _)| key.name() == Some("other"))
+        .unwrap();
+    assert_eq!(key.kind(), "MutAppState");
+    assert_eq!(key.type_name(), std::any::type_name::<Separate>());
+
+    let name = std::any::type_name::<Separate>();
+    assert!(stats.to_prometheus().contains(&format!(
+        "app_state_lock_acquisitions_total{{state=\"{name}\",kind=\"MutAppState\",name=\"other\"}} 2\n"
+    )));
+}
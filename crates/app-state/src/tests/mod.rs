@@ -1,6 +1,25 @@
+#[cfg(feature = "tokio")]
+mod asynchronous;
+#[cfg(feature = "serde")]
+mod config_tests;
 mod default_init_tests;
+mod factory_tests;
 mod init_tests;
+mod initialization_tests;
+mod isolation_tests;
+mod lifecycle_tests;
 mod lock;
+#[cfg(feature = "metrics")]
+mod metrics_tests;
 mod mutable;
+mod named_tests;
+#[cfg(feature = "serde")]
+mod persistent_tests;
 mod readonly;
+mod rw;
+#[cfg(feature = "serde")]
+mod snapshot_tests;
+mod store_tests;
+mod swap;
+mod transaction_tests;
 mod util;
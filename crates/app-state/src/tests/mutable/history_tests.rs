@@ -0,0 +1,124 @@
+use crate::{AppStateTrait, HistoryMutAppState, DEFAULT_HISTORY_LIMIT};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Document {
+    lines: Vec<String>,
+}
+
+fn push(doc: &HistoryMutAppState<Document>, line: &str) {
+    doc.get_mut().lines.push(line.to_string());
+}
+
+fn lines(doc: &HistoryMutAppState<Document>) -> Vec<String> {
+    doc.get_mut().lines.clone()
+}
+
+#[crate::test]
+fn test_undo_redo() {
+    HistoryMutAppState::init(Document::default());
+    let doc = HistoryMutAppState::<Document>::get();
+    push(&doc, "first");
+    push(&doc, "second");
+    assert_eq!(doc.history_len(), 2);
+
+    assert!(doc.undo());
+    assert_eq!(lines(&doc), vec!["first"]);
+    assert!(doc.undo());
+    assert!(lines(&doc).is_empty());
+    assert!(!doc.undo());
+    assert_eq!(doc.redo_len(), 2);
+
+    assert!(doc.redo());
+    assert!(doc.redo());
+    assert_eq!(lines(&doc), vec!["first", "second"]);
+    assert!(!doc.redo());
+    assert_eq!(doc.history_len(), 2);
+}
+
+#[crate::test]
+fn test_reading_is_not_recorded() {
+    let doc = HistoryMutAppState::<Document>::get_or_insert_default();
+    assert!(doc.get_mut().lines.is_empty());
+    assert_eq!(doc.history_len(), 0);
+}
+
+#[crate::test]
+fn test_change_discards_redo() {
+    let doc = HistoryMutAppState::<Document>::get_or_insert_default();
+    push(&doc, "first");
+    push(&doc, "second");
+    doc.undo();
+
+    push(&doc, "other");
+    assert_eq!(doc.redo_len(), 0);
+    assert!(!doc.redo());
+    assert_eq!(lines(&doc), vec!["first", "other"]);
+}
+
+#[crate::test]
+fn test_group() {
+    let doc = HistoryMutAppState::<Document>::get_or_insert_default();
+    push(&doc, "first");
+    {
+        let _group = doc.begin_group();
+        push(&doc, "second");
+        {
+            let _nested = doc.begin_group();
+            push(&doc, "third");
+        }
+        push(&doc, "fourth");
+        assert_eq!(doc.history_len(), 1);
+    }
+
+    assert_eq!(doc.history_len(), 2);
+    doc.undo();
+    assert_eq!(lines(&doc), vec!["first"]);
+    doc.redo();
+    assert_eq!(lines(&doc), vec!["first", "second", "third", "fourth"]);
+}
+
+#[crate::test]
+fn test_empty_group() {
+    let doc = HistoryMutAppState::<Document>::get_or_insert_default();
+    drop(doc.begin_group());
+    assert_eq!(doc.history_len(), 0);
+}
+
+#[crate::test]
+fn test_history_limit() {
+    let doc = HistoryMutAppState::<Document>::get_or_insert_default();
+    for i in 0..DEFAULT_HISTORY_LIMIT + 5 {
+        push(&doc, &i.to_string());
+    }
+    assert_eq!(doc.history_len(), DEFAULT_HISTORY_LIMIT);
+
+    doc.set_history_limit(2);
+    assert_eq!(doc.history_len(), 2);
+    push(&doc, "last");
+    assert_eq!(doc.history_len(), 2);
+
+    while doc.undo() {}
+    assert_eq!(lines(&doc).len(), DEFAULT_HISTORY_LIMIT + 4);
+
+    doc.clear_history();
+    assert_eq!(doc.history_len(), 0);
+    assert_eq!(doc.redo_len(), 0);
+}
+
+#[crate::test]
+fn test_panic_is_not_recorded() {
+    let doc = HistoryMutAppState::<Document>::get_or_insert_default();
+    push(&doc, "first");
+    assert!(doc.undo());
+
+    let cloned = doc.clone();
+    assert!(std::panic::catch_unwind(move || {
+        let mut lock = cloned.get_mut();
+        lock.lines.push("incomplete".to_string());
+        panic!("Panic while changing the document");
+    })
+    .is_err());
+
+    assert_eq!(doc.history_len(), 0);
+    assert_eq!(doc.redo_len(), 1);
+}
@@ -58,6 +58,7 @@ fn test_get_or_insert() {
 }
 
 #[test]
+#[allow(clippy::redundant_closure)]
 fn test_get_or_insert_with() {
     create_creatable_state!();
 
@@ -72,3 +73,42 @@ fn test_get_or_insert_default() {
     let state = MutAppState::<State>::get_or_insert_default();
     assert_eq!(state.get_mut().name, "Hello".to_string());
 }
+
+#[test]
+fn test_take_state() {
+    create_state!(MutAppState);
+
+    let state = MutAppState::<State>::take();
+    assert_eq!(state.unwrap().ok().unwrap().name, "Hello".to_string());
+    assert!(MutAppState::<State>::try_get().is_err());
+    assert!(MutAppState::<State>::take().is_none());
+}
+
+#[test]
+fn test_take_state_with_handles() {
+    create_state!(MutAppState);
+
+    let handle = MutAppState::<State>::get();
+    let state = MutAppState::<State>::take().unwrap();
+    assert!(state.is_err());
+    assert!(MutAppState::<State>::try_get().is_err());
+
+    handle.get_mut().name = "Changed".to_string();
+    let remaining = state.err().unwrap();
+    assert_eq!(remaining.get_mut().name, "Changed".to_string());
+
+    drop(handle);
+    let inner = std::sync::Arc::try_unwrap(remaining.into_inner())
+        .ok()
+        .unwrap();
+    assert_eq!(inner.into_inner().unwrap().name, "Changed".to_string());
+}
+
+#[test]
+fn test_remove_mutable_state() {
+    create_state!(MutAppState);
+
+    let state = MutAppState::<State>::remove();
+    assert_eq!(state.unwrap().get_mut().name, "Hello".to_string());
+    assert!(MutAppState::<State>::try_get().is_err());
+}
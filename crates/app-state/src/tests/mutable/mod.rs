@@ -1,2 +1,6 @@
+mod history_tests;
 mod injection_tests;
 mod manual_tests;
+mod poison_tests;
+mod subscription_tests;
+mod version_tests;
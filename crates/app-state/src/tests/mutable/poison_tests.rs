@@ -0,0 +1,164 @@
+use crate::{
+    default_poison_policy, init_default_mut_state, stateful, AppStateTrait, MutAppState,
+    MutAppStateLock, PoisonPolicy, StateError,
+};
+
+#[derive(Default)]
+struct Counter {
+    value: u32,
+}
+
+#[init_default_mut_state]
+#[derive(Default)]
+struct DefaultCounter {
+    value: u32,
+}
+
+fn poison(state: &MutAppState<Counter>) {
+    let cloned = state.clone();
+    std::thread::spawn(move || {
+        let mut lock = cloned.get_mut();
+        lock.value += 1;
+        panic!("Poisoning the state");
+    })
+    .join()
+    .unwrap_err();
+}
+
+#[stateful]
+fn increment(mut counter: MutAppStateLock<Counter>) -> u32 {
+    counter.value += 1;
+    counter.value
+}
+
+#[crate::test]
+fn test_default_policy() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    assert_eq!(state.poison_policy(), default_poison_policy());
+
+    poison(&state);
+    assert!(matches!(
+        MutAppStateLock::try_lock(&state).err(),
+        Some(StateError::Poisoned { .. })
+    ));
+    let cloned = state.clone();
+    assert!(std::panic::catch_unwind(move || cloned.try_get_mut().is_some()).is_err());
+    assert!(std::panic::catch_unwind(increment).is_err());
+}
+
+#[crate::test]
+fn test_get_mut_checked() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    state.set_poison_policy(PoisonPolicy::ReturnError);
+
+    // Waits for the state to be unlocked
+    let lock = state.get_mut();
+    let cloned = state.clone();
+    let handle = std::thread::spawn(move || cloned.get_mut_checked().map(|lock| lock.value));
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    drop(lock);
+    assert_eq!(handle.join().unwrap(), Ok(0));
+
+    poison(&state);
+    assert!(matches!(
+        state.get_mut_checked().err(),
+        Some(StateError::Poisoned { .. })
+    ));
+}
+
+#[crate::test]
+fn test_recover_policy() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    state.set_poison_policy(PoisonPolicy::Recover);
+
+    poison(&state);
+    assert_eq!(increment(), 2);
+    assert!(!state.is_poisoned());
+}
+
+#[crate::test]
+fn test_reset_to_default_policy() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    state.reset_to_default_on_poison();
+    state.get_mut().value = 5;
+
+    poison(&state);
+    assert_eq!(state.try_get_mut().unwrap().value, 0);
+    assert_eq!(increment(), 1);
+}
+
+#[crate::test]
+fn test_reset_to_default_of_default_state() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    state.set_poison_policy(PoisonPolicy::ResetToDefault);
+    state.get_mut().value = 5;
+
+    poison(&state);
+    assert_eq!(state.try_get_mut().unwrap().value, 0);
+}
+
+#[test]
+fn test_reset_to_default_of_default_init_state() {
+    let state = MutAppState::<DefaultCounter>::get();
+    state.set_poison_policy(PoisonPolicy::ResetToDefault);
+    state.get_mut().value = 5;
+
+    let cloned = state.clone();
+    std::thread::spawn(move || {
+        let _lock = cloned.get_mut();
+        panic!("Poisoning the state");
+    })
+    .join()
+    .unwrap_err();
+    assert_eq!(state.try_get_mut().unwrap().value, 0);
+}
+
+#[crate::test]
+fn test_reset_to_default_without_default() {
+    MutAppState::init(Counter::default());
+    let state = MutAppState::<Counter>::get();
+
+    let cloned = state.clone();
+    assert!(std::panic::catch_unwind(move || {
+        cloned.set_poison_policy(PoisonPolicy::ResetToDefault)
+    })
+    .is_err());
+    assert_eq!(state.poison_policy(), default_poison_policy());
+}
+
+#[crate::test]
+fn test_panic_policy() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    state.set_poison_policy(PoisonPolicy::Panic);
+
+    poison(&state);
+    let cloned = state.clone();
+    assert!(std::panic::catch_unwind(move || cloned.try_get_mut().is_some()).is_err());
+}
+
+#[crate::test]
+fn test_clear_poison() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+
+    poison(&state);
+    state.clear_poison();
+    assert_eq!(state.try_get_mut().unwrap().value, 1);
+}
+
+#[crate::test]
+fn test_policy_is_shared_between_handles() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    MutAppState::<Counter>::get().set_poison_policy(PoisonPolicy::Recover);
+
+    assert_eq!(state.poison_policy(), PoisonPolicy::Recover);
+}
+
+#[crate::test]
+fn test_policy_is_not_shared_with_converted_handles() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    let converted = MutAppState::from(state.clone().into_inner());
+    converted.set_poison_policy(PoisonPolicy::Recover);
+
+    assert_eq!(converted.poison_policy(), PoisonPolicy::Recover);
+    assert_ne!(state.poison_policy(), PoisonPolicy::Recover);
+}
@@ -0,0 +1,114 @@
+use crate::{stateful, AppStateTrait, MutAppState, MutAppStateLock};
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct Counter {
+    value: u32,
+}
+
+fn record(state: &MutAppState<Counter>) -> (Arc<Mutex<Vec<u32>>>, crate::Subscription<Counter>) {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let seen_clone = seen.clone();
+    let subscription = state.subscribe(move |counter| {
+        seen_clone.lock().unwrap().push(counter.value);
+    });
+
+    (seen, subscription)
+}
+
+#[stateful]
+fn increment(mut counter: MutAppStateLock<Counter>) {
+    counter.value += 1;
+}
+
+#[crate::test]
+fn test_notify_on_change() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    let (seen, _subscription) = record(&state);
+
+    {
+        let mut lock = state.get_mut();
+        lock.value += 1;
+        lock.value += 1;
+        // Subscribers are notified once the lock is released
+        assert!(seen.lock().unwrap().is_empty());
+    }
+
+    increment();
+    assert_eq!(*seen.lock().unwrap(), vec![2, 3]);
+}
+
+#[crate::test]
+fn test_no_notification_without_change() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    let (seen, _subscription) = record(&state);
+
+    assert_eq!(state.get_mut().value, 0);
+    drop(state.try_get_mut().unwrap());
+    assert!(seen.lock().unwrap().is_empty());
+}
+
+#[crate::test]
+fn test_unsubscribe() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    let (seen, subscription) = record(&state);
+    let (other, _other_subscription) = record(&state);
+
+    state.get_mut().value = 1;
+    subscription.unsubscribe();
+    state.get_mut().value = 2;
+
+    assert_eq!(*seen.lock().unwrap(), vec![1]);
+    assert_eq!(*other.lock().unwrap(), vec![1, 2]);
+}
+
+#[crate::test]
+fn test_dropping_subscription_keeps_subscribed() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    let (seen, subscription) = record(&state);
+
+    drop(subscription);
+    state.get_mut().value = 1;
+    assert_eq!(*seen.lock().unwrap(), vec![1]);
+}
+
+#[crate::test]
+fn test_into_inner() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    let (seen, _subscription) = record(&state);
+
+    let mut lock = state.get_mut();
+    lock.value = 1;
+    let mut guard = lock.into_inner();
+    guard.value = 2;
+    drop(guard);
+
+    assert_eq!(*seen.lock().unwrap(), vec![1]);
+}
+
+#[crate::test]
+fn test_no_notification_on_panic() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    let (seen, _subscription) = record(&state);
+
+    let cloned = state.clone();
+    std::thread::spawn(move || {
+        let mut lock = cloned.get_mut();
+        lock.value += 1;
+        panic!("Poisoning the state");
+    })
+    .join()
+    .unwrap_err();
+
+    assert!(seen.lock().unwrap().is_empty());
+}
+
+#[crate::test]
+fn test_no_notify_on_change_through_converted_handle() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    let (seen, _subscription) = record(&state);
+
+    let converted = MutAppState::from(state.clone().into_inner());
+    converted.get_mut().value += 1;
+    assert!(seen.lock().unwrap().is_empty());
+}
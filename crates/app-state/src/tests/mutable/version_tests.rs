@@ -0,0 +1,88 @@
+use crate::{AppStateTrait, MutAppState, StateError};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Counter {
+    value: u32,
+}
+
+#[crate::test]
+fn test_version_incremented_on_change() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    assert_eq!(state.version(), 0);
+
+    state.get_mut().value += 1;
+    assert_eq!(state.version(), 1);
+
+    let lock = state.get_mut();
+    assert_eq!(lock.value, 1);
+    drop(lock);
+    assert_eq!(state.version(), 1);
+}
+
+#[crate::test]
+fn test_version_shared_by_clones() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    let other = MutAppState::<Counter>::get();
+    other.get_mut().value = 5;
+    assert_eq!(state.version(), 1);
+    assert_eq!(state.clone().version(), 1);
+}
+
+#[crate::test]
+fn test_lock_version_excludes_own_changes() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    let mut lock = state.get_mut();
+    lock.value = 1;
+    assert_eq!(lock.version(), 0);
+    drop(lock);
+    assert_eq!(state.version(), 1);
+}
+
+#[crate::test]
+fn test_compare_and_update() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    let (version, counter) = {
+        let lock = state.get_mut();
+        (lock.version(), lock.clone())
+    };
+
+    let res = state.compare_and_update(version, |c| {
+        c.value = counter.value + 1;
+        c.value
+    });
+    assert_eq!(res, Ok(1));
+    assert_eq!(state.version(), version + 1);
+}
+
+#[crate::test]
+fn test_compare_and_update_conflict() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    let version = state.version();
+    state.get_mut().value = 10;
+
+    let res = state.compare_and_update(version, |c| c.value = 1);
+    assert_eq!(
+        res,
+        Err(StateError::VersionMismatch {
+            type_name: std::any::type_name::<Counter>(),
+            expected: 0,
+            actual: 1,
+        })
+    );
+    assert_eq!(state.get_mut().value, 10);
+    assert_eq!(state.version(), 1);
+}
+
+#[crate::test]
+fn test_version_is_not_shared_with_converted_handles() {
+    let state = MutAppState::<Counter>::get_or_insert_default();
+    state.get_mut().value = 1;
+
+    let converted = MutAppState::from(state.clone().into_inner());
+    assert_eq!(converted.version(), 0);
+    converted.get_mut().value = 10;
+
+    assert_eq!(converted.version(), 1);
+    assert_eq!(state.version(), 1);
+    assert_eq!(state.get_mut().value, 10);
+}
@@ -0,0 +1,148 @@
+use crate::tests::util::StateTrait;
+use crate::{
+    create_creatable_state, stateful, AppState, AppStateTrait, MutAppState, MutAppStateLock,
+    StateError, StateStore,
+};
+
+struct Pool {
+    url: String,
+}
+
+#[crate::test]
+fn test_named_states() {
+    AppState::init_named(
+        "primary",
+        Pool {
+            url: "primary".to_string(),
+        },
+    );
+    AppState::init_named(
+        "replica",
+        Pool {
+            url: "replica".to_string(),
+        },
+    );
+
+    assert_eq!(AppState::<Pool>::get_named("primary").url, "primary");
+    assert_eq!(AppState::<Pool>::get_named("replica").url, "replica");
+    assert!(AppState::<Pool>::try_get().is_err());
+}
+
+#[crate::test]
+fn test_named_and_unnamed_states() {
+    AppState::init(Pool {
+        url: "default".to_string(),
+    });
+    AppState::init_named(
+        "replica",
+        Pool {
+            url: "replica".to_string(),
+        },
+    );
+
+    assert_eq!(AppState::<Pool>::get().url, "default");
+    assert_eq!(AppState::<Pool>::get_named("replica").url, "replica");
+
+    assert!(AppState::<Pool>::remove_named("replica").is_some());
+    assert!(AppState::<Pool>::try_get_named("replica").is_err());
+    assert_eq!(AppState::<Pool>::get().url, "default");
+}
+
+#[crate::test]
+fn test_non_existent_named_state() {
+    AppState::init(Pool {
+        url: "default".to_string(),
+    });
+
+    assert_eq!(
+        AppState::<Pool>::try_get_named("replica").err(),
+        Some(StateError::NotFound {
+            type_name: std::any::type_name::<AppState<Pool>>(),
+            name: Some("replica".to_string()),
+        })
+    );
+}
+
+#[test]
+fn test_named_mutable_state() {
+    create_creatable_state!();
+
+    let first = MutAppState::<State>::get_or_insert_default_named("first");
+    let second = MutAppState::<State>::get_or_insert_with_named("second", State::default);
+    first.get_mut().set_name("Changed");
+
+    assert_eq!(first.get_mut().get_name(), "Changed");
+    assert_eq!(second.get_mut().get_name(), "Hello");
+}
+
+#[test]
+fn test_named_states_in_store() {
+    let store = StateStore::new();
+    store.init_named::<AppState<_>, _>(
+        "replica",
+        Pool {
+            url: "replica".to_string(),
+        },
+    );
+
+    assert_eq!(store.get_named::<AppState<Pool>>("replica").url, "replica");
+    assert!(store.try_get::<AppState<Pool>>().is_err());
+    assert!(store.remove_named::<AppState<Pool>>("replica").is_some());
+}
+
+#[stateful(named(primary = "primary", replica = "replica"))]
+fn check_named_pools(primary: AppState<Pool>, replica: AppState<Pool>) {
+    assert_eq!(primary.url, "primary");
+    assert_eq!(replica.url, "replica");
+}
+
+#[crate::test]
+fn test_inject_named_states() {
+    AppState::init_named(
+        "primary",
+        Pool {
+            url: "primary".to_string(),
+        },
+    );
+    AppState::init_named(
+        "replica",
+        Pool {
+            url: "replica".to_string(),
+        },
+    );
+
+    check_named_pools();
+}
+
+#[stateful(init(state), named(state = "named"))]
+fn init_named_state<T: StateTrait + Default>(mut state: MutAppStateLock<T>) {
+    assert_eq!(state.get_name(), "Hello");
+    state.set_name("Changed");
+}
+
+#[test]
+fn test_init_injected_named_state() {
+    create_creatable_state!();
+
+    init_named_state::<State>();
+    assert_eq!(
+        MutAppState::<State>::get_named("named")
+            .get_mut()
+            .get_name(),
+        "Changed"
+    );
+    assert!(MutAppState::<State>::try_get().is_err());
+}
+
+#[stateful(named(_pool = "missing"))]
+fn check_missing_named_pool(_pool: AppState<Pool>) {}
+
+#[crate::test]
+#[should_panic]
+fn test_inject_non_existent_named_state() {
+    AppState::init(Pool {
+        url: "default".to_string(),
+    });
+
+    check_missing_named_pool();
+}
@@ -0,0 +1,197 @@
+use crate::{shutdown, JsonFormat, PersistentMutAppState, SaveMode, StateError};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Settings {
+    volume: u32,
+    theme: String,
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("app-state-{}-{}", std::process::id(), name));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+fn read_settings(path: &PathBuf) -> Settings {
+    serde_json::from_slice(&fs::read(path).unwrap()).unwrap()
+}
+
+#[crate::test]
+fn test_load_default_if_missing() {
+    let path = temp_path("missing.json");
+    let state =
+        PersistentMutAppState::<Settings>::open::<JsonFormat>(&path, SaveMode::OnShutdown).unwrap();
+
+    assert_eq!(*state.get_mut(), Settings::default());
+    assert!(!path.exists());
+}
+
+#[crate::test]
+fn test_load_existing_file() {
+    let path = temp_path("existing.json");
+    fs::write(&path, r#"{ "volume": 3, "theme": "dark" }"#).unwrap();
+
+    PersistentMutAppState::<Settings>::init::<JsonFormat>(&path, SaveMode::OnShutdown).unwrap();
+    let state = PersistentMutAppState::<Settings>::get();
+
+    assert_eq!(state.get_mut().volume, 3);
+    assert_eq!(state.get_mut().theme, "dark");
+    fs::remove_file(&path).unwrap();
+}
+
+#[crate::test]
+fn test_save_on_release() {
+    let path = temp_path("release.json");
+    let state =
+        PersistentMutAppState::<Settings>::open::<JsonFormat>(&path, SaveMode::OnRelease).unwrap();
+
+    // Reading does not write the file
+    assert_eq!(state.get_mut().volume, 0);
+    assert!(!path.exists());
+
+    state.get_mut().volume = 7;
+    assert_eq!(read_settings(&path).volume, 7);
+    assert!(!state.is_dirty());
+
+    let mut file_name = path.file_name().unwrap().to_os_string();
+    file_name.push(".tmp");
+    assert!(!path.with_file_name(file_name).exists());
+    fs::remove_file(&path).unwrap();
+}
+
+#[crate::test]
+fn test_no_save_on_panic() {
+    let path = temp_path("panic.json");
+    let state =
+        PersistentMutAppState::<Settings>::open::<JsonFormat>(&path, SaveMode::OnRelease).unwrap();
+    state.get_mut().volume = 7;
+
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let mut lock = state.get_mut();
+        lock.volume = 9;
+        panic!("Aborting the change");
+    }));
+
+    assert!(res.is_err());
+    assert_eq!(read_settings(&path).volume, 7);
+    assert!(!state.is_dirty());
+    fs::remove_file(&path).unwrap();
+}
+
+#[crate::test]
+fn test_save_on_interval() {
+    let path = temp_path("interval.json");
+    let state = PersistentMutAppState::<Settings>::open::<JsonFormat>(
+        &path,
+        SaveMode::Interval(Duration::from_millis(10)),
+    )
+    .unwrap();
+
+    state.get_mut().volume = 5;
+    assert!(state.is_dirty());
+
+    let start = Instant::now();
+    while state.is_dirty() && start.elapsed() < Duration::from_secs(5) {
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    assert!(!state.is_dirty());
+    assert_eq!(read_settings(&path).volume, 5);
+    fs::remove_file(&path).unwrap();
+}
+
+#[crate::test]
+fn test_save_on_shutdown() {
+    let path = temp_path("shutdown.json");
+    let state =
+        PersistentMutAppState::<Settings>::init::<JsonFormat>(&path, SaveMode::OnShutdown).unwrap();
+
+    state.get_mut().theme = "light".to_string();
+    assert!(!path.exists());
+
+    shutdown();
+    assert_eq!(read_settings(&path).theme, "light");
+    fs::remove_file(&path).unwrap();
+}
+
+#[crate::test]
+fn test_save_on_drop() {
+    let path = temp_path("drop.json");
+    let state =
+        PersistentMutAppState::<Settings>::open::<JsonFormat>(&path, SaveMode::OnShutdown).unwrap();
+
+    state.get_mut().volume = 9;
+    drop(state);
+
+    assert_eq!(read_settings(&path).volume, 9);
+    fs::remove_file(&path).unwrap();
+}
+
+#[crate::test]
+fn test_invalid_file() {
+    let path = temp_path("invalid.json");
+    fs::write(&path, "not json").unwrap();
+
+    let res = PersistentMutAppState::<Settings>::open::<JsonFormat>(&path, SaveMode::OnRelease);
+    assert!(matches!(res, Err(StateError::Deserialization { .. })));
+    // The invalid file is left untouched
+    assert_eq!(fs::read_to_string(&path).unwrap(), "not json");
+    fs::remove_file(&path).unwrap();
+}
+
+#[crate::test]
+fn test_write_error() {
+    let path = temp_path("missing-dir").join("settings.json");
+    let state =
+        PersistentMutAppState::<Settings>::open::<JsonFormat>(&path, SaveMode::OnRelease).unwrap();
+
+    state.get_mut().volume = 1;
+    assert!(matches!(
+        state.last_error(),
+        Some(StateError::Persistence { path: error_path, .. }) if error_path == path
+    ));
+    assert!(state.last_error().is_none());
+    assert!(state.save().is_err());
+}
+
+#[cfg(feature = "toml")]
+#[crate::test]
+fn test_toml_format() {
+    let path = temp_path("settings.toml");
+    let state =
+        PersistentMutAppState::<Settings>::open::<crate::TomlFormat>(&path, SaveMode::OnRelease)
+            .unwrap();
+    state.get_mut().theme = "dark".to_string();
+    assert!(fs::read_to_string(&path)
+        .unwrap()
+        .contains("theme = \"dark\""));
+    drop(state);
+
+    let state =
+        PersistentMutAppState::<Settings>::open::<crate::TomlFormat>(&path, SaveMode::OnRelease)
+            .unwrap();
+    assert_eq!(state.get_mut().theme, "dark");
+    fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "bincode")]
+#[crate::test]
+fn test_bincode_format() {
+    let path = temp_path("settings.bin");
+    let state =
+        PersistentMutAppState::<Settings>::open::<crate::BincodeFormat>(&path, SaveMode::OnRelease)
+            .unwrap();
+    state.get_mut().volume = 42;
+    drop(state);
+
+    let state =
+        PersistentMutAppState::<Settings>::open::<crate::BincodeFormat>(&path, SaveMode::OnRelease)
+            .unwrap();
+    assert_eq!(state.get_mut().volume, 42);
+    fs::remove_file(&path).unwrap();
+}
@@ -1,5 +1,5 @@
 use crate::tests::util::StateTrait;
-use crate::{create_creatable_state, create_state, AppState, AppStateTrait};
+use crate::{clear_all, create_creatable_state, create_state, AppState, AppStateTrait};
 
 struct NonExistentState {}
 
@@ -43,6 +43,7 @@ fn test_get_or_insert() {
 }
 
 #[test]
+#[allow(clippy::redundant_closure)]
 fn test_get_or_insert_with() {
     create_creatable_state!();
 
@@ -57,3 +58,36 @@ fn test_get_or_insert_default() {
     let state = AppState::<State>::get_or_insert_default();
     assert_eq!(state.name, "Hello".to_string());
 }
+
+#[test]
+fn test_remove_state() {
+    create_state!(AppState);
+
+    let state = AppState::<State>::remove();
+    assert_eq!(state.unwrap().name, "Hello".to_string());
+    assert!(AppState::<State>::try_get().is_err());
+    assert!(AppState::<State>::remove().is_none());
+}
+
+#[test]
+fn test_remove_state_keeps_handles() {
+    create_state!(AppState);
+
+    let state = AppState::<State>::get();
+    AppState::<State>::remove();
+    assert_eq!(state.name, "Hello".to_string());
+
+    AppState::init(State {
+        name: "Changed".to_string(),
+    });
+    assert_eq!(state.name, "Hello".to_string());
+    assert_eq!(AppState::<State>::get().name, "Changed".to_string());
+}
+
+#[crate::test]
+fn test_clear_all() {
+    create_state!(AppState);
+
+    clear_all();
+    assert!(AppState::<State>::try_get().is_err());
+}
@@ -1,2 +1,3 @@
 mod injection_tests;
 mod manual_tests;
+mod trait_object_tests;
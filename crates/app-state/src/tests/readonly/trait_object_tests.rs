@@ -0,0 +1,67 @@
+use crate::tests::util::StateTrait;
+use crate::{create_creatable_state, stateful, AppState, AppStateTrait, StateStore};
+use std::sync::Arc;
+
+trait Storage: Send + Sync {
+    fn name(&self) -> String;
+}
+
+struct InMemoryStorage;
+
+impl Storage for InMemoryStorage {
+    fn name(&self) -> String {
+        "in-memory".to_string()
+    }
+}
+
+struct DiskStorage;
+
+impl Storage for DiskStorage {
+    fn name(&self) -> String {
+        "disk".to_string()
+    }
+}
+
+#[stateful]
+fn check_storage(storage: AppState<dyn Storage>, expected: &str) {
+    assert_eq!(storage.name(), expected);
+}
+
+#[crate::test]
+fn test_trait_object_state() {
+    AppState::<dyn Storage>::init_arc(Arc::new(InMemoryStorage));
+    assert_eq!(AppState::<dyn Storage>::get().name(), "in-memory");
+    assert!(AppState::<InMemoryStorage>::try_get().is_err());
+}
+
+#[crate::test]
+fn test_swap_trait_object_state() {
+    AppState::<dyn Storage>::init_arc(Arc::new(InMemoryStorage));
+    check_storage("in-memory");
+
+    AppState::<dyn Storage>::init_arc(Arc::new(DiskStorage));
+    check_storage("disk");
+}
+
+#[crate::test]
+fn test_named_trait_object_state() {
+    AppState::<dyn Storage>::init_arc_named("disk", Arc::new(DiskStorage));
+    assert_eq!(AppState::<dyn Storage>::get_named("disk").name(), "disk");
+    assert!(AppState::<dyn Storage>::try_get().is_err());
+}
+
+#[test]
+fn test_trait_object_state_in_store() {
+    let store = StateStore::new();
+    store.init_arc::<dyn Storage>(Arc::new(DiskStorage));
+    assert_eq!(store.get::<AppState<dyn Storage>>().name(), "disk");
+}
+
+#[test]
+fn test_trait_object_from_existing_state() {
+    create_creatable_state!();
+
+    let state: Arc<dyn StateTrait> = Arc::new(State::default());
+    AppState::<dyn StateTrait>::init_arc(state);
+    assert_eq!(AppState::<dyn StateTrait>::get().get_name(), "Hello");
+}
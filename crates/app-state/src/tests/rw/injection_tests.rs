@@ -0,0 +1,69 @@
+use crate::tests::util::StateTrait;
+use crate::{
+    create_creatable_state, create_state, stateful, AppStateTrait, RwAppState, RwAppStateReadLock,
+    RwAppStateWriteLock,
+};
+
+struct NonExistentState {}
+
+#[stateful]
+fn check_rw_state<T: StateTrait>(state: RwAppState<T>) {
+    assert_eq!(state.read().get_name(), "Hello");
+}
+
+#[stateful]
+fn check_rw_state_with_read_lock<T: StateTrait>(state: RwAppStateReadLock<T>) {
+    assert_eq!(state.get_name(), "Hello");
+}
+
+#[stateful]
+fn change_name_with_write_lock<T: StateTrait>(mut state: RwAppStateWriteLock<T>) {
+    state.set_name("Changed");
+}
+
+#[stateful]
+fn check_rw_state_changed<T: StateTrait>(state: RwAppStateReadLock<T>) {
+    assert_eq!(state.get_name(), "Changed");
+}
+
+#[stateful]
+fn check_non_existent_rw_state(_state: RwAppStateReadLock<NonExistentState>) {}
+
+#[stateful(init(state))]
+fn init_check_and_mutate_state<T: StateTrait + Default>(mut state: RwAppStateWriteLock<T>) {
+    assert_eq!(state.get_name(), "Hello");
+    state.set_name("Changed");
+}
+
+#[test]
+fn test_get_rw_state() {
+    create_state!(RwAppState);
+    check_rw_state::<State>();
+}
+
+#[test]
+fn test_get_rw_state_with_read_lock() {
+    create_state!(RwAppState);
+    check_rw_state_with_read_lock::<State>();
+}
+
+#[test]
+fn test_change_rw_state_with_write_lock() {
+    create_state!(RwAppState);
+    change_name_with_write_lock::<State>();
+    check_rw_state_changed::<State>();
+}
+
+#[test]
+#[should_panic]
+fn test_get_non_existent_rw_state() {
+    create_state!(RwAppState);
+    check_non_existent_rw_state();
+}
+
+#[test]
+fn test_init_check_and_mutate_state() {
+    create_creatable_state!();
+    init_check_and_mutate_state::<State>();
+    check_rw_state_changed::<State>();
+}
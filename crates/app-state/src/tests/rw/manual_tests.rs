@@ -0,0 +1,70 @@
+use crate::tests::util::StateTrait;
+use crate::{create_creatable_state, create_state, AppStateTrait, RwAppState};
+use std::thread;
+
+struct NonExistentState {}
+
+#[test]
+fn test_get_rw_state() {
+    create_state!(RwAppState);
+    let state = RwAppState::<State>::get();
+    assert_eq!(state.read().name, "Hello".to_string());
+}
+
+#[test]
+fn test_try_get_rw_state() {
+    create_state!(RwAppState);
+    let state = RwAppState::<State>::try_get();
+    assert!(state.is_ok());
+    assert_eq!(state.unwrap().read().name, "Hello".to_string());
+}
+
+#[test]
+#[should_panic]
+fn test_get_non_existent_rw_state() {
+    create_state!(RwAppState);
+    RwAppState::<NonExistentState>::get();
+}
+
+#[test]
+fn test_try_get_non_existent_rw_state() {
+    create_state!(RwAppState);
+    let state = RwAppState::<NonExistentState>::try_get();
+    assert!(state.is_err());
+}
+
+#[test]
+fn test_change_rw_state() {
+    create_state!(RwAppState);
+
+    let state = RwAppState::<State>::get();
+    let mut lock = state.write();
+    assert_eq!(lock.name, "Hello".to_string());
+    lock.name = "Changed".to_string();
+    drop(lock);
+
+    let state = RwAppState::<State>::get();
+    assert_eq!(state.read().name, "Changed".to_string());
+}
+
+#[test]
+fn test_concurrent_reads() {
+    create_state!(RwAppState);
+
+    let state = RwAppState::<State>::get();
+    let lock = state.read();
+    let cloned = state.clone();
+    thread::spawn(move || assert_eq!(cloned.read().name, "Hello".to_string()))
+        .join()
+        .unwrap();
+
+    assert_eq!(lock.name, "Hello".to_string());
+}
+
+#[test]
+fn test_get_or_insert_default() {
+    create_creatable_state!();
+
+    let state = RwAppState::<State>::get_or_insert_default();
+    assert_eq!(state.read().name, "Hello".to_string());
+}
@@ -0,0 +1,196 @@
+use crate::{
+    register_serializable, restore, snapshot, AppState, AppStateTrait, InitMutAppState,
+    MutAppState, PoisonPolicy, RwAppState, Snapshot, StateError, SwapAppState,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Config {
+    url: String,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Counter {
+    count: u32,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Theme(String);
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Version(u32);
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, InitMutAppState)]
+#[app_state(serialize)]
+struct Derived {
+    value: u32,
+}
+
+fn register_all() {
+    register_serializable::<AppState<Config>>();
+    register_serializable::<MutAppState<Counter>>();
+    register_serializable::<RwAppState<Theme>>();
+    register_serializable::<SwapAppState<Version>>();
+}
+
+#[crate::test]
+fn test_snapshot_and_restore() {
+    register_all();
+    AppState::init(Config {
+        url: "localhost".to_string(),
+    });
+    MutAppState::init(Counter { count: 1 });
+    RwAppState::init(Theme("dark".to_string()));
+    SwapAppState::init(Version(1));
+
+    let snapshot = snapshot().unwrap();
+
+    AppState::init(Config {
+        url: "remote".to_string(),
+    });
+    MutAppState::<Counter>::get().get_mut().count = 2;
+    RwAppState::<Theme>::get().write().0 = "light".to_string();
+    SwapAppState::<Version>::get().store(Version(2));
+
+    restore(&snapshot).unwrap();
+    assert_eq!(AppState::<Config>::get().url, "localhost");
+    assert_eq!(MutAppState::<Counter>::get().get_mut().count, 1);
+    assert_eq!(RwAppState::<Theme>::get().read().0, "dark");
+    assert_eq!(SwapAppState::<Version>::get().load().0, 1);
+}
+
+#[crate::test]
+fn test_snapshot_format() {
+    register_all();
+    MutAppState::init(Counter { count: 3 });
+
+    let snapshot = snapshot().unwrap();
+    assert_eq!(
+        serde_json::to_value(&snapshot).unwrap(),
+        serde_json::json!({ std::any::type_name::<Counter>(): { "count": 3 } })
+    );
+    assert_eq!(
+        snapshot.get::<Counter>(),
+        Some(&serde_json::json!({ "count": 3 }))
+    );
+}
+
+#[crate::test]
+fn test_restore_from_document() {
+    register_all();
+
+    let snapshot = serde_json::from_value::<Snapshot>(serde_json::json!({
+        std::any::type_name::<Theme>(): "blue",
+        std::any::type_name::<Version>(): 7,
+    }))
+    .unwrap();
+
+    restore(&snapshot).unwrap();
+    assert_eq!(RwAppState::<Theme>::get().read().0, "blue");
+    assert_eq!(SwapAppState::<Version>::get().load().0, 7);
+    assert!(AppState::<Config>::try_get().is_err());
+}
+
+#[crate::test]
+fn test_uninitialized_states_are_skipped() {
+    register_all();
+    SwapAppState::init(Version(1));
+
+    let snapshot = snapshot().unwrap();
+    assert_eq!(snapshot.iter().count(), 1);
+    assert!(snapshot.get::<Version>().is_some());
+    assert!(snapshot.get::<Config>().is_none());
+}
+
+#[crate::test]
+fn test_unregistered_states_are_not_included() {
+    MutAppState::init(Counter { count: 1 });
+
+    let snapshot = snapshot().unwrap();
+    assert!(snapshot.get::<Counter>().is_none());
+}
+
+#[crate::test]
+fn test_restore_unknown_state() {
+    register_all();
+    MutAppState::init(Counter { count: 1 });
+
+    let snapshot = serde_json::from_value::<Snapshot>(serde_json::json!({
+        std::any::type_name::<Counter>(): { "count": 5 },
+        "unknown::State": {},
+    }))
+    .unwrap();
+
+    assert_eq!(
+        restore(&snapshot),
+        Err(StateError::UnknownState {
+            type_name: "unknown::State".to_string()
+        })
+    );
+    assert_eq!(MutAppState::<Counter>::get().get_mut().count, 1);
+}
+
+#[crate::test]
+fn test_restore_invalid_state() {
+    register_all();
+    MutAppState::init(Counter { count: 1 });
+
+    let snapshot = serde_json::from_value::<Snapshot>(serde_json::json!({
+        std::any::type_name::<Counter>(): { "count": 5 },
+        std::any::type_name::<Version>(): "not a number",
+    }))
+    .unwrap();
+
+    match restore(&snapshot) {
+        Err(StateError::Deserialization { type_name, .. }) => {
+            assert_eq!(type_name, std::any::type_name::<Version>())
+        }
+        res => panic!("Unexpected result: {res:?}"),
+    }
+    assert_eq!(MutAppState::<Counter>::get().get_mut().count, 1);
+}
+
+#[crate::test]
+fn test_snapshot_poisoned_state() {
+    register_all();
+    let counter = MutAppState::<Counter>::get_or_insert_default();
+    counter.set_poison_policy(PoisonPolicy::ReturnError);
+
+    let cloned = counter.clone();
+    std::thread::spawn(move || {
+        let _lock = cloned.get_mut();
+        panic!("Poisoning the state");
+    })
+    .join()
+    .unwrap_err();
+
+    assert_eq!(snapshot(), Err(StateError::poisoned::<Counter>()));
+}
+
+#[crate::test]
+fn test_derived_state() {
+    Derived { value: 4 }.init_mut_app_state();
+
+    let snapshot = snapshot().unwrap();
+    assert_eq!(
+        snapshot.get::<Derived>(),
+        Some(&serde_json::json!({ "value": 4 }))
+    );
+
+    MutAppState::<Derived>::get().get_mut().value = 0;
+    restore(&snapshot).unwrap();
+    assert_eq!(MutAppState::<Derived>::get().get_mut().value, 4);
+}
+
+#[crate::test]
+fn test_register_different_kind_of_state() {
+    register_all();
+    register_serializable::<MutAppState<Counter>>();
+    register_serializable::<AppState<Counter>>();
+
+    let conflict = StateError::SerializationConflict {
+        type_name: std::any::type_name::<Counter>(),
+    };
+    assert_eq!(snapshot().err(), Some(conflict.clone()));
+    assert_eq!(restore(&Snapshot::default()).err(), Some(conflict));
+}
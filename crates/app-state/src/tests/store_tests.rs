@@ -0,0 +1,128 @@
+use crate::tests::util::StateTrait;
+use crate::{
+    create_creatable_state, AppState, AppStateTrait, MutAppState, MutAppStateLock, StateError,
+    StateStore,
+};
+
+#[test]
+fn test_independent_stores() {
+    create_creatable_state!();
+
+    let first = StateStore::new();
+    let second = StateStore::new();
+    first.init::<AppState<_>, _>(State {
+        name: "First".to_string(),
+    });
+    second.init::<AppState<_>, _>(State {
+        name: "Second".to_string(),
+    });
+
+    assert_eq!(first.get::<AppState<State>>().get_name(), "First");
+    assert_eq!(second.get::<AppState<State>>().get_name(), "Second");
+    assert!(AppState::<State>::try_get().is_err());
+}
+
+#[test]
+fn test_try_get_from_empty_store() {
+    create_creatable_state!();
+
+    let store = StateStore::new();
+    assert!(store.try_get::<AppState<State>>().is_err());
+    assert!(store.try_get::<MutAppState<State>>().is_err());
+}
+
+#[test]
+#[should_panic]
+fn test_get_from_empty_store() {
+    create_creatable_state!();
+
+    StateStore::new().get::<AppState<State>>();
+}
+
+#[test]
+fn test_mutable_state_in_store() {
+    create_creatable_state!();
+
+    let store = StateStore::new();
+    store.init::<MutAppState<_>, _>(State::default());
+
+    let state = store.get::<MutAppState<State>>();
+    let mut lock = MutAppStateLock::new(&state);
+    lock.set_name("Changed");
+    drop(lock);
+
+    assert_eq!(
+        store.get::<MutAppState<State>>().get_mut().get_name(),
+        "Changed"
+    );
+}
+
+#[test]
+fn test_get_or_insert_in_store() {
+    create_creatable_state!();
+
+    let store = StateStore::new();
+    let state = store.get_or_insert_default::<AppState<State>, _>();
+    assert_eq!(state.get_name(), "Hello");
+
+    let state = store.get_or_insert::<AppState<State>, _>(State {
+        name: "Changed".to_string(),
+    });
+    assert_eq!(state.get_name(), "Hello");
+
+    store.init_if_not_exists::<MutAppState<_>, _, _>(State::default);
+    let state = store.get_or_insert_with::<MutAppState<State>, _, _>(|| State {
+        name: "Changed".to_string(),
+    });
+    assert_eq!(state.get_mut().get_name(), "Hello");
+}
+
+#[test]
+fn test_global_store() {
+    create_creatable_state!();
+
+    AppState::init(State::default());
+    assert_eq!(
+        StateStore::global().get::<AppState<State>>().get_name(),
+        "Hello"
+    );
+}
+
+#[test]
+fn test_state_errors() {
+    create_creatable_state!();
+
+    let store = StateStore::new();
+    assert_eq!(
+        store.try_get::<AppState<State>>().err(),
+        Some(StateError::StoreNotInitialized)
+    );
+
+    store.init::<MutAppState<_>, _>(State::default());
+    assert_eq!(
+        store.try_get::<AppState<State>>().err(),
+        Some(StateError::NotFound {
+            type_name: std::any::type_name::<AppState<State>>(),
+            name: None,
+        })
+    );
+}
+
+#[test]
+fn test_poisoned_state() {
+    create_creatable_state!();
+
+    let state = MutAppState::<State>::get_or_insert_default();
+    let cloned = state.clone();
+    std::thread::spawn(move || {
+        let _lock = cloned.get_mut();
+        panic!("Poisoning the state");
+    })
+    .join()
+    .unwrap_err();
+
+    assert!(matches!(
+        MutAppStateLock::lock_checked(&state).err(),
+        Some(StateError::Poisoned { .. })
+    ));
+}
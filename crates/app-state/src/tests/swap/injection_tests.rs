@@ -0,0 +1,36 @@
+use crate::tests::util::StateTrait;
+use crate::{create_creatable_state, create_state, stateful, AppStateTrait, SwapAppState};
+
+struct NonExistentState {}
+
+#[stateful]
+fn check_swap_state<T: StateTrait>(state: SwapAppState<T>) {
+    assert_eq!(state.load().get_name(), "Hello");
+}
+
+#[stateful]
+fn check_non_existent_swap_state(_state: SwapAppState<NonExistentState>) {}
+
+#[stateful(init(state))]
+fn init_and_check_state<T: StateTrait + Default>(state: SwapAppState<T>) {
+    assert_eq!(state.load().get_name(), "Hello");
+}
+
+#[test]
+fn test_get_injected_swap_state() {
+    create_state!(SwapAppState);
+    check_swap_state::<State>();
+}
+
+#[test]
+#[should_panic]
+fn test_get_non_existent_swap_state() {
+    create_state!(SwapAppState);
+    check_non_existent_swap_state();
+}
+
+#[test]
+fn test_init_default_swap_state() {
+    create_creatable_state!();
+    init_and_check_state::<State>();
+}
@@ -0,0 +1,66 @@
+use crate::tests::util::StateTrait;
+use crate::{create_creatable_state, create_state, AppStateTrait, SwapAppState};
+use std::thread;
+
+struct NonExistentState {}
+
+#[test]
+fn test_get_swap_state() {
+    create_state!(SwapAppState);
+    let state = SwapAppState::<State>::get();
+    assert_eq!(state.load().name, "Hello".to_string());
+}
+
+#[test]
+fn test_try_get_non_existent_swap_state() {
+    create_state!(SwapAppState);
+    assert!(SwapAppState::<NonExistentState>::try_get().is_err());
+}
+
+#[test]
+fn test_store_swap_state() {
+    create_state!(SwapAppState);
+
+    let state = SwapAppState::<State>::get();
+    let snapshot = state.load();
+    state.store(State {
+        name: "Changed".to_string(),
+    });
+
+    assert_eq!(snapshot.name, "Hello".to_string());
+    assert_eq!(
+        SwapAppState::<State>::get().load().name,
+        "Changed".to_string()
+    );
+}
+
+#[test]
+fn test_rcu_swap_state() {
+    create_state!(SwapAppState);
+
+    let state = SwapAppState::<State>::get();
+    let threads = (0..4)
+        .map(|_| {
+            let state = state.clone();
+            thread::spawn(move || {
+                state.rcu(|old| State {
+                    name: format!("{}!", old.name),
+                });
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(state.load().name, "Hello!!!!".to_string());
+}
+
+#[test]
+fn test_get_or_insert_default() {
+    create_creatable_state!();
+
+    let state = SwapAppState::<State>::get_or_insert_default();
+    assert_eq!(state.load().name, "Hello".to_string());
+}
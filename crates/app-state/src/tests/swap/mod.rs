@@ -0,0 +1,2 @@
+mod injection_tests;
+mod manual_tests;
@@ -0,0 +1,184 @@
+use crate::{transaction, AppStateTrait, MutAppState, StateError, MAX_TRANSACTION_ATTEMPTS};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Account {
+    balance: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Ledger {
+    entries: Vec<u32>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Error {
+    InsufficientFunds,
+    State(StateError),
+}
+
+impl From<StateError> for Error {
+    fn from(err: StateError) -> Self {
+        Error::State(err)
+    }
+}
+
+fn init(balance: u32) {
+    MutAppState::init(Account { balance });
+    MutAppState::init(Ledger::default());
+}
+
+fn withdraw(amount: u32) -> Result<u32, Error> {
+    transaction(|tx| {
+        let mut account = tx.lock::<Account>();
+        let mut ledger = tx.lock::<Ledger>();
+        ledger.entries.push(amount);
+        account.balance = account
+            .balance
+            .checked_sub(amount)
+            .ok_or(Error::InsufficientFunds)?;
+        Ok(account.balance)
+    })
+}
+
+fn balance() -> u32 {
+    MutAppState::<Account>::get().get_mut().balance
+}
+
+fn entries() -> Vec<u32> {
+    MutAppState::<Ledger>::get().get_mut().entries.clone()
+}
+
+#[crate::test]
+fn test_commit() {
+    init(10);
+    assert_eq!(withdraw(4), Ok(6));
+    assert_eq!(balance(), 6);
+    assert_eq!(entries(), vec![4]);
+}
+
+#[crate::test]
+fn test_rollback_on_err() {
+    init(10);
+    assert_eq!(withdraw(20), Err(Error::InsufficientFunds));
+    assert_eq!(balance(), 10);
+    assert!(entries().is_empty());
+}
+
+#[crate::test]
+fn test_rollback_on_panic() {
+    init(10);
+    let res = catch_unwind(AssertUnwindSafe(|| {
+        transaction(|tx| -> Result<(), StateError> {
+            tx.lock::<Ledger>().entries.push(1);
+            tx.lock::<Account>().balance = 0;
+            panic!("Aborting the transaction");
+        })
+    }));
+
+    assert!(res.is_err());
+    assert_eq!(balance(), 10);
+    assert!(entries().is_empty());
+}
+
+#[crate::test]
+fn test_working_copies() {
+    init(10);
+    transaction(|tx| -> Result<(), StateError> {
+        tx.lock::<Account>().balance = 5;
+
+        // The working copy is reused, the state is not changed yet
+        assert_eq!(tx.lock::<Account>().balance, 5);
+        assert_eq!(balance(), 10);
+        Ok(())
+    })
+    .unwrap();
+
+    assert_eq!(balance(), 5);
+}
+
+#[crate::test]
+fn test_retry_on_conflict() {
+    init(10);
+    let mut attempts = 0;
+    let res = transaction(|tx| -> Result<u32, StateError> {
+        attempts += 1;
+        let mut account = tx.lock::<Account>();
+        if attempts == 1 {
+            // Simulates a concurrent change
+            MutAppState::<Account>::get().get_mut().balance = 20;
+        }
+
+        account.balance += 1;
+        Ok(account.balance)
+    });
+
+    assert_eq!(attempts, 2);
+    assert_eq!(res, Ok(21));
+    assert_eq!(balance(), 21);
+}
+
+#[crate::test]
+fn test_retry_on_replaced_state() {
+    init(10);
+    let mut attempts = 0;
+    let res = transaction(|tx| -> Result<u32, StateError> {
+        attempts += 1;
+        let mut account = tx.lock::<Account>();
+        if attempts == 1 {
+            // The working copy belongs to the replaced state
+            MutAppState::init(Account { balance: 20 });
+        }
+
+        account.balance += 1;
+        Ok(account.balance)
+    });
+
+    assert_eq!(attempts, 2);
+    assert_eq!(res, Ok(21));
+    assert_eq!(balance(), 21);
+}
+
+#[crate::test]
+fn test_abort_on_repeated_conflicts() {
+    init(10);
+    let mut attempts = 0;
+    let res = transaction(|tx| -> Result<(), StateError> {
+        attempts += 1;
+        tx.lock::<Account>().balance = 0;
+        MutAppState::<Account>::get().get_mut().balance += 1;
+        Ok(())
+    });
+
+    assert_eq!(attempts, MAX_TRANSACTION_ATTEMPTS);
+    assert_eq!(
+        res,
+        Err(StateError::TransactionConflict {
+            attempts: MAX_TRANSACTION_ATTEMPTS
+        })
+    );
+    assert_eq!(balance(), 10 + MAX_TRANSACTION_ATTEMPTS as u32);
+}
+
+#[crate::test]
+fn test_lock_twice() {
+    init(10);
+    transaction(|tx| -> Result<(), StateError> {
+        let _account = tx.lock::<Account>();
+        assert!(matches!(
+            tx.try_lock::<Account>(),
+            Err(StateError::WouldBlock { .. })
+        ));
+        Ok(())
+    })
+    .unwrap();
+}
+
+#[crate::test]
+fn test_uninitialized_state() {
+    #[derive(Clone)]
+    struct Missing;
+
+    let res = transaction(|tx| tx.try_lock::<Missing>().map(|_| ()));
+    assert!(res.is_err());
+}
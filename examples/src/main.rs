@@ -6,6 +6,7 @@ use log::LevelFilter;
 use log4rs::append::console::ConsoleAppender;
 use log4rs::config::{Appender, Root};
 use log4rs::Config;
+use std::sync::Arc;
 
 #[ctor::ctor]
 fn init_logger() {
@@ -95,6 +96,11 @@ fn with_generic<T: GetName>(state: AppState<T>) {
     assert_eq!(state.get_ref().get_name(), "Hello".to_string());
 }
 
+#[stateful]
+fn with_trait_object(state: AppState<dyn GetName>) {
+    assert_eq!(state.get_name(), "Hello".to_string());
+}
+
 struct Test;
 
 impl Test {
@@ -120,7 +126,12 @@ fn main() {
     }
     .init_app_state();
 
+    AppState::<dyn GetName>::init_arc(Arc::new(State {
+        name: "Hello".to_string(),
+    }));
+
     with_generic::<State>();
+    with_trait_object();
     Test.test_fn("".to_string());
     Test::other_test_fn("".to_string());
 